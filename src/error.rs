@@ -15,6 +15,14 @@ pub enum StarTrustError {
     TryFromPrimitiveError(String),
     #[error("GeneralError: {0}")]
     GeneralError(String),
+    #[error("SaveFileError: {0}")]
+    SaveFileError(String),
+    #[error("TranscriptError: {0}")]
+    TranscriptError(String),
+    #[error("ConfigError: {0}")]
+    ConfigError(String),
+    #[error("CoordinateError: {0}")]
+    CoordinateError(String),
 }
 
 impl From<std::io::Error> for StarTrustError {
@@ -41,6 +49,12 @@ impl<T: TryFromPrimitive> From<TryFromPrimitiveError<T>> for StarTrustError {
     }
 }
 
+impl From<serde_json::Error> for StarTrustError {
+    fn from(value: serde_json::Error) -> Self {
+        StarTrustError::SaveFileError(format!("{}", value))
+    }
+}
+
 pub type StResult<T> = std::result::Result<T, StarTrustError>;
 
 // impl <T> From<std::io::Result<T>> for StResult<T> {
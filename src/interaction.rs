@@ -12,7 +12,10 @@ use num_traits::{Num, ToPrimitive};
 use termcolor::{Color, ColorSpec, WriteColor};
 
 use crate::error::StarTrustError;
-#[allow(unused_imports)]
+use crate::render::render_events;
+use crate::the_game::{
+    fan, fnd, Action, ComputerRequest, LandingCraft, PhaserTargeting, PlanetOrder, MAX_SPREAD,
+};
 use crate::{StResult, TheGame};
 
 const ESC_KEY: u8 = 27; /* 'ESC' key code */
@@ -30,6 +33,7 @@ pub fn delay(ms: usize) {
     thread::sleep(Duration::from_millis(ms as u64));
 }
 
+#[allow(clippy::unbuffered_bytes)] // `sin` is always a `BufRead` in practice
 fn getbyte<R: Read>(sin: &mut R) -> StResult<Option<u8>> {
     sin.bytes().next().transpose().map_err(|e| {
         let e: StarTrustError = e.into();
@@ -94,7 +98,6 @@ fn clearkeyboard<R: BufRead>(_stdin: &mut R) -> StResult<()> {
 /// Description:  Gets a line of text from the stream and strips the
 /// non-printing characters at the end of the line
 /// Includes:     stdio.h
-#[allow(dead_code)]
 pub fn fgetline<R: BufRead>(_stream: &mut R) -> Result<String, StarTrustError> {
     let mut buff = String::new();
     let j = _stream.read_line(&mut buff)?;
@@ -155,14 +158,10 @@ pub fn buzz() {
 /// Check for valid input characters
 fn charokay(cc: u8, mode: InputMode) -> bool {
     match mode {
-        InputMode::Mode0 => (cc >= (b' ')) && (cc <= ASCHI),
-        InputMode::Mode1 => (cc >= b'A') && (cc <= b'Z') || (cc == b'*') || (cc == b' '),
-        InputMode::Mode2 => {
-            ((cc >= b'0') && (cc <= b'9')) || (cc == b'.') || (cc == b',') || (cc == b'-')
-        }
-        InputMode::Mode3 => {
-            ((cc >= b'A') && (cc <= b'Z')) || ((cc >= b'0') && (cc <= b'9')) || (cc == b' ')
-        }
+        InputMode::Mode0 => (b' '..=ASCHI).contains(&cc),
+        InputMode::Mode1 => cc.is_ascii_uppercase() || (cc == b'*') || (cc == b' '),
+        InputMode::Mode2 => cc.is_ascii_digit() || (cc == b'.') || (cc == b',') || (cc == b'-'),
+        InputMode::Mode3 => cc.is_ascii_uppercase() || cc.is_ascii_digit() || (cc == b' '),
         InputMode::InvalidMode => false,
     }
 } /* End charokay */
@@ -213,6 +212,7 @@ impl InputValue {
 }
 
 /**  ********************************************************************
+```text
     Function:     getinp
     Argument(s):  input buffer, maximum input length, mode
     Description:  Gets input from console, echoing to the current screen
@@ -237,6 +237,7 @@ impl InputValue {
                   terminal.  Calls beep and buzz.
     Returns:      0 for successful read; 1 for CR only; -1 for ESC
     Includes:     conio.h
+```
 */
 pub fn getinp<R: BufRead, W: WriteColor>(
     sin: &mut R,
@@ -382,6 +383,458 @@ pub fn getwarp<R: BufRead, W: WriteColor>(sin: &mut R, sout: &mut W) -> StResult
     })
 } /* End getwarp */
 
+/// Ask for a course and warp, and turn them into an order to move
+pub fn get_warp_action<R: BufRead, W: WriteColor>(
+    the_game: &TheGame,
+    sin: &mut R,
+    sout: &mut W,
+) -> StResult<Option<Action>> {
+    let mut warp = 0f64;
+    let mut course;
+
+    loop {
+        loop {
+            course = getcourse(sin, sout)?;
+            if course < 9.0 {
+                break;
+            }
+            beep();
+        }
+        if course >= 1.0 {
+            loop {
+                warp = getwarp(sin, sout)?;
+                if (warp <= 0.0) || (warp > 12.0) {
+                    course = 10.0;
+                    break;
+                }
+                let rejection = the_game.validate(&Action::Warp { course, warp });
+                if rejection.is_empty() {
+                    break;
+                }
+                render_events(sout, &rejection)?;
+                beep();
+            }
+        }
+        if course < 9.0 {
+            break;
+        }
+    }
+    Ok(if course < 1.0 {
+        // Abort move
+        None
+    } else {
+        Some(Action::Warp { course, warp })
+    })
+}
+
+/// Ask how much energy to fire, and turn it into an order to fire the phasers
+pub fn get_phasers_action<R: BufRead, W: WriteColor>(
+    the_game: &TheGame,
+    sin: &mut R,
+    sout: &mut W,
+) -> StResult<Option<Action>> {
+    let rejection = the_game.validate(&Action::Phasers {
+        energy: 0.0,
+        targeting: PhaserTargeting::Even,
+    });
+    if !rejection.is_empty() {
+        // Phasers inoperative
+        render_events(sout, &rejection)?;
+        return Ok(None);
+    }
+    let mut targeting = PhaserTargeting::Even;
+    if the_game.klingons().len() > 1 {
+        loop {
+            write!(sout, "TARGETING: EVEN, BY DISTANCE OR MANUAL (E/D/M)? ")?;
+            sout.flush()?;
+            let gb = getinp(sin, sout, 1, InputMode::Mode1)?;
+            writeln!(sout)?;
+            match gb {
+                InputValue::InputString(ibuff) if ibuff == "E" => {}
+                InputValue::InputString(ibuff) if ibuff == "D" => {
+                    targeting = PhaserTargeting::Weighted
+                }
+                InputValue::InputString(ibuff) if ibuff == "M" => {
+                    return get_manual_phasers_action(the_game, sin, sout)
+                }
+                InputValue::InputString(_) => {
+                    beep();
+                    continue;
+                }
+                InputValue::Blank => {}
+                InputValue::Esc => return Ok(None),
+            }
+            break;
+        }
+    }
+    let mut x;
+    loop {
+        write!(sout, "PHASERS READY: ENERGY UNITS TO FIRE? ")?;
+        sout.flush()?;
+        let gb = getinp(sin, sout, 15, InputMode::Mode2)?;
+        writeln!(sout)?;
+        if let InputValue::InputString(ibuff) = gb {
+            x = ibuff.parse()?;
+        } else {
+            x = 0.0;
+            break;
+        }
+        let rejection = the_game.validate(&Action::Phasers {
+            energy: x,
+            targeting: targeting.clone(),
+        });
+        if rejection.is_empty() {
+            break;
+        }
+        render_events(sout, &rejection)?;
+    }
+    Ok(Some(Action::Phasers {
+        energy: x,
+        targeting,
+    }))
+}
+
+/// Ask how much energy to fire at each Klingon in turn
+fn get_manual_phasers_action<R: BufRead, W: WriteColor>(
+    the_game: &TheGame,
+    sin: &mut R,
+    sout: &mut W,
+) -> StResult<Option<Action>> {
+    loop {
+        let mut amounts = Vec::new();
+        for klingon in the_game.klingons() {
+            let sector = klingon.sector();
+            loop {
+                write!(
+                    sout,
+                    "ENERGY TO KLINGON AT SECTOR {} - {} (DISTANCE {:.2})? ",
+                    sector.row() + 1,
+                    sector.column() + 1,
+                    fnd(sector, the_game.sector())
+                )?;
+                sout.flush()?;
+                let gb = getinp(sin, sout, 15, InputMode::Mode2)?;
+                writeln!(sout)?;
+                match gb {
+                    InputValue::InputString(ibuff) => match ibuff.parse() {
+                        Ok(amount) => amounts.push((klingon.id(), amount)),
+                        // Not a number: ask again for this one
+                        Err(_) => {
+                            beep();
+                            continue;
+                        }
+                    },
+                    // Leave this one alone
+                    InputValue::Blank => {}
+                    InputValue::Esc => return Ok(None),
+                }
+                break;
+            }
+        }
+        let action = Action::Phasers {
+            energy: amounts.iter().map(|(_, amount)| amount).sum(),
+            targeting: PhaserTargeting::Manual(amounts),
+        };
+        let rejection = the_game.validate(&action);
+        if rejection.is_empty() {
+            return Ok(Some(action));
+        }
+        render_events(sout, &rejection)?;
+    }
+}
+
+/// Ask whether the shields should be up, and how much energy to move into them
+pub fn get_shields_action<R: BufRead, W: WriteColor>(
+    the_game: &TheGame,
+    sin: &mut R,
+    sout: &mut W,
+) -> StResult<Option<Action>> {
+    let mut up = the_game.shields.is_up();
+    loop {
+        write!(sout, "SHIELDS UP OR DOWN (U/D)? ")?;
+        sout.flush()?;
+        let gb = getinp(sin, sout, 1, InputMode::Mode1)?;
+        writeln!(sout)?;
+        match gb {
+            InputValue::InputString(ibuff) if ibuff == "U" => up = true,
+            InputValue::InputString(ibuff) if ibuff == "D" => up = false,
+            InputValue::InputString(_) => {
+                beep();
+                continue;
+            }
+            // Leave them as they are
+            InputValue::Blank => {}
+            InputValue::Esc => return Ok(None),
+        }
+        break;
+    }
+    let mut transfer;
+    loop {
+        write!(sout, "ENERGY TO SHIELDS (NEGATIVE TO DRAW BACK)? ")?;
+        sout.flush()?;
+        let gb = getinp(sin, sout, 15, InputMode::Mode2)?;
+        writeln!(sout)?;
+        if let InputValue::InputString(ibuff) = gb {
+            // Not a number: ask again rather than give up the whole game
+            transfer = match ibuff.parse() {
+                Ok(transfer) => transfer,
+                Err(_) => {
+                    beep();
+                    continue;
+                }
+            };
+        } else {
+            transfer = 0.0;
+            break;
+        }
+        let rejection = the_game.validate(&Action::Shields { up, transfer });
+        if rejection.is_empty() {
+            break;
+        }
+        render_events(sout, &rejection)?;
+    }
+    Ok(Some(Action::Shields { up, transfer }))
+}
+
+/// Ask which of the library computer's functions is wanted
+pub fn get_computer_action<R: BufRead, W: WriteColor>(
+    the_game: &TheGame,
+    sin: &mut R,
+    sout: &mut W,
+) -> StResult<Option<Action>> {
+    let rejection = the_game.validate(&Action::Computer(ComputerRequest::StatusReport));
+    if !rejection.is_empty() {
+        // Computer damaged
+        render_events(sout, &rejection)?;
+        return Ok(None);
+    }
+    loop {
+        write!(sout, "COMPUTER FUNCTION? ")?;
+        sout.flush()?;
+        let gb = getinp(sin, sout, 2, InputMode::Mode2)?;
+        writeln!(sout)?;
+        let request = match gb {
+            InputValue::InputString(ibuff) if ibuff == "1" => ComputerRequest::TorpedoData,
+            InputValue::InputString(ibuff) if ibuff == "2" => {
+                let from = match get_coordinates(sin, sout, "FROM")? {
+                    Some(from) => from,
+                    None => return Ok(None),
+                };
+                let to = match get_coordinates(sin, sout, "TO")? {
+                    Some(to) => to,
+                    None => return Ok(None),
+                };
+                ComputerRequest::Calculator { from, to }
+            }
+            InputValue::InputString(ibuff) if ibuff == "3" => ComputerRequest::NearestStarbase,
+            InputValue::InputString(ibuff) if ibuff == "4" => ComputerRequest::StatusReport,
+            InputValue::InputString(_) | InputValue::Blank => {
+                writeln!(sout, "  1 = TORPEDO DATA")?;
+                writeln!(sout, "  2 = COURSE AND DISTANCE CALCULATOR")?;
+                writeln!(sout, "  3 = NEAREST STARBASE")?;
+                writeln!(sout, "  4 = STATUS REPORT\n")?;
+                continue;
+            }
+            InputValue::Esc => return Ok(None),
+        };
+        return Ok(Some(Action::Computer(request)));
+    }
+}
+
+/// Ask for a sector or quadrant as "ROW,COLUMN", returning `None` if the captain gives up
+fn get_coordinates<R: BufRead, W: WriteColor>(
+    sin: &mut R,
+    sout: &mut W,
+    which: &str,
+) -> StResult<Option<(i32, i32)>> {
+    loop {
+        write!(sout, "{} (ROW,COLUMN)? ", which)?;
+        sout.flush()?;
+        let gb = getinp(sin, sout, 7, InputMode::Mode2)?;
+        writeln!(sout)?;
+        let ibuff = match gb {
+            InputValue::InputString(ibuff) => ibuff,
+            InputValue::Blank | InputValue::Esc => return Ok(None),
+        };
+        let mut parts = ibuff.split(',').map(|part| part.trim().parse::<i32>());
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(row)), Some(Ok(column)), None) => return Ok(Some((row, column))),
+            _ => beep(),
+        }
+    }
+}
+
+/// Ask for a course, and turn it into an order to fire a photon torpedo
+pub fn get_torpedo_action<R: BufRead, W: WriteColor>(
+    the_game: &TheGame,
+    sin: &mut R,
+    sout: &mut W,
+) -> StResult<Option<Action>> {
+    let rejection = the_game.validate(&Action::Torpedo { course: 0.0 });
+    if !rejection.is_empty() {
+        // Torpedoes damaged, or none left
+        render_events(sout, &rejection)?;
+        return Ok(None);
+    }
+    let count = if the_game.photon_torpedoes() > 1 {
+        match get_spread_size(the_game, sin, sout)? {
+            Some(count) => count,
+            None => return Ok(None),
+        }
+    } else {
+        1
+    };
+    if count == 1 {
+        return Ok(
+            get_torpedo_course(sin, sout, "TORPEDO ")?.map(|course| Action::Torpedo { course })
+        );
+    }
+    let mut fanned = true;
+    loop {
+        write!(sout, "FAN OR SEPARATE COURSES (F/S)? ")?;
+        sout.flush()?;
+        let gb = getinp(sin, sout, 1, InputMode::Mode1)?;
+        writeln!(sout)?;
+        match gb {
+            InputValue::InputString(ibuff) if ibuff == "F" => {}
+            InputValue::InputString(ibuff) if ibuff == "S" => fanned = false,
+            InputValue::InputString(_) => {
+                beep();
+                continue;
+            }
+            InputValue::Blank => {}
+            InputValue::Esc => return Ok(None),
+        }
+        break;
+    }
+    let courses = if fanned {
+        let course = match get_torpedo_course(sin, sout, "CENTER TORPEDO ")? {
+            Some(course) => course,
+            None => return Ok(None),
+        };
+        write!(sout, "FAN ANGLE? ")?;
+        sout.flush()?;
+        let gb = getinp(sin, sout, 4, InputMode::Mode2)?;
+        writeln!(sout)?;
+        let angle = match gb {
+            InputValue::InputString(ibuff) => ibuff.parse().unwrap_or(0.0),
+            InputValue::Blank => 0.0,
+            InputValue::Esc => return Ok(None),
+        };
+        fan(course, count, angle)
+    } else {
+        let mut courses = Vec::new();
+        for i in 1..=count {
+            match get_torpedo_course(sin, sout, &format!("TORPEDO {} ", i))? {
+                Some(course) => courses.push(course),
+                None => return Ok(None),
+            }
+        }
+        courses
+    };
+    Ok(Some(Action::TorpedoSpread { courses }))
+}
+
+/// Ask for a course, and turn it into an order to launch a deep-space probe
+pub fn get_probe_action<R: BufRead, W: WriteColor>(
+    the_game: &TheGame,
+    sin: &mut R,
+    sout: &mut W,
+) -> StResult<Option<Action>> {
+    let rejection = the_game.validate(&Action::Probe { course: 0.0 });
+    if !rejection.is_empty() {
+        // Long-range sensors damaged, or no probes left
+        render_events(sout, &rejection)?;
+        return Ok(None);
+    }
+    Ok(get_torpedo_course(sin, sout, "PROBE ")?.map(|course| Action::Probe { course }))
+}
+
+/// Ask what to do about a planet
+pub fn get_planet_action<R: BufRead, W: WriteColor>(
+    sin: &mut R,
+    sout: &mut W,
+) -> StResult<Option<Action>> {
+    loop {
+        write!(sout, "PLANET FUNCTION? ")?;
+        sout.flush()?;
+        let gb = getinp(sin, sout, 2, InputMode::Mode2)?;
+        writeln!(sout)?;
+        let order = match gb {
+            InputValue::InputString(ibuff) if ibuff == "1" => PlanetOrder::Orbit,
+            InputValue::InputString(ibuff) if ibuff == "2" => {
+                PlanetOrder::Transport(LandingCraft::Transporter)
+            }
+            InputValue::InputString(ibuff) if ibuff == "3" => {
+                PlanetOrder::Transport(LandingCraft::Shuttle)
+            }
+            InputValue::InputString(ibuff) if ibuff == "4" => PlanetOrder::Mine,
+            InputValue::InputString(ibuff) if ibuff == "5" => PlanetOrder::UseCrystals,
+            InputValue::InputString(_) | InputValue::Blank => {
+                writeln!(sout, "  1 = STANDARD ORBIT")?;
+                writeln!(sout, "  2 = TRANSPORTER")?;
+                writeln!(sout, "  3 = SHUTTLE CRAFT")?;
+                writeln!(sout, "  4 = MINE DILITHIUM")?;
+                writeln!(sout, "  5 = USE DILITHIUM CRYSTALS\n")?;
+                continue;
+            }
+            InputValue::Esc => return Ok(None),
+        };
+        return Ok(Some(Action::Planet(order)));
+    }
+}
+
+/// Ask how many torpedoes to fire at once, returning `None` if the captain gives up
+fn get_spread_size<R: BufRead, W: WriteColor>(
+    the_game: &TheGame,
+    sin: &mut R,
+    sout: &mut W,
+) -> StResult<Option<usize>> {
+    loop {
+        write!(sout, "NUMBER OF TORPEDOES (1-{})? ", MAX_SPREAD)?;
+        sout.flush()?;
+        let gb = getinp(sin, sout, 1, InputMode::Mode2)?;
+        writeln!(sout)?;
+        let count = match gb {
+            InputValue::InputString(ibuff) => ibuff.parse().unwrap_or(0),
+            InputValue::Blank => 1,
+            InputValue::Esc => return Ok(None),
+        };
+        if !(1..=MAX_SPREAD).contains(&count) {
+            beep();
+            continue;
+        }
+        let rejection = the_game.validate(&Action::TorpedoSpread {
+            courses: vec![1.0; count],
+        });
+        if rejection.is_empty() {
+            return Ok(Some(count));
+        }
+        render_events(sout, &rejection)?;
+    }
+}
+
+/// Ask for the course of one torpedo, returning `None` if the firing is aborted
+fn get_torpedo_course<R: BufRead, W: WriteColor>(
+    sin: &mut R,
+    sout: &mut W,
+    which: &str,
+) -> StResult<Option<f64>> {
+    let mut course = 10.0;
+    while course >= 9.0 {
+        write!(sout, "{}", which)?;
+        sout.flush()?;
+
+        course = getcourse(sin, sout)?;
+    }
+    Ok(if course < 1.0 {
+        // Abort firing of torpedo
+        None
+    } else {
+        Some(course)
+    })
+}
+
 /// Draw one number in one color
 pub fn draw_number_in_color<W: WriteColor>(
     sout: &mut W,
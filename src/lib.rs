@@ -5,11 +5,21 @@ extern crate dimensioned as dim;
 
 pub use error::{StResult, StarTrustError};
 pub use interaction::{clrscr, yesno};
+pub use render::render_events;
 pub use stinstr::{show_instructions, show_title};
-pub use the_game::{TheGame, TheGameDefs, TheGameDefsBuilder};
+pub use the_game::{
+    Action, Component, ComponentStatus, ComputerRequest, Condition, Difficulty, Ending, GameConfig,
+    GameEvent, GameState, LandingCraft, PhaserTargeting, Planet, PlanetClass, PlanetOrder,
+    Quadrant, QuadrantContents, Romulan, Sector, SectorContents, ShortRangeScan, StarDate, TheGame,
+    TheGameDefs, TheGameDefsBuilder, TorpedoSolution, Weapon,
+};
+pub use transcript::{Recorder, Replayer, Transcript};
+pub use util::RandomMode;
 
 mod error;
 mod interaction;
+mod render;
 mod stinstr;
 mod the_game;
+mod transcript;
 mod util;
@@ -12,15 +12,17 @@
 
 extern crate startrust;
 
-use std::io::{stdin, Write};
+use std::fs::File;
+use std::io::{stdin, BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
 
 use clap::{crate_authors, crate_description, crate_version, Clap};
 use log::{debug, LevelFilter};
 use termcolor::{ColorChoice, StandardStream, WriteColor};
 
 use startrust::{
-    clrscr, show_instructions, show_title, yesno, StResult, StarTrustError, TheGame, TheGameDefs,
-    TheGameDefsBuilder,
+    clrscr, show_instructions, show_title, yesno, Difficulty, GameConfig, RandomMode, Recorder,
+    StResult, StarTrustError, TheGame, TheGameDefs, TheGameDefsBuilder, Transcript,
 };
 
 #[derive(Clap)]
@@ -35,15 +37,106 @@ struct GetOpts {
     /// Run with debug output
     #[clap(short, long)]
     debug: bool,
+    /// Seed for the random number generator, to replay a particular game
+    #[clap(long)]
+    seed: Option<u64>,
+    /// Random number generator: "standard", or "classic" for the one from the original game
+    #[clap(long, possible_values = &["standard", "classic"])]
+    rng: Option<RandomMode>,
+    /// How hard the game is
+    #[clap(long, possible_values = &["cadet", "lieutenant", "captain", "admiral"])]
+    difficulty: Option<Difficulty>,
+    /// Read game settings from this TOML file
+    #[clap(long)]
+    config: Option<PathBuf>,
+    /// Initial energy (overrides the configuration file)
+    #[clap(long)]
+    energy: Option<f64>,
+    /// Initial photon torpedoes (overrides the configuration file)
+    #[clap(long)]
+    torpedoes: Option<i32>,
+    /// Years to destroy the Klingons in (overrides the configuration file)
+    #[clap(long)]
+    years: Option<i32>,
+    /// Pick up a game saved with the SAVE command
+    #[clap(long)]
+    load: Option<PathBuf>,
+    /// Record each game's transcript to this file (later games go to "<name>-2", "<name>-3", ...)
+    #[clap(long, conflicts_with = "load")]
+    record: Option<PathBuf>,
+    /// Replay a game recorded with --record
+    #[clap(long, conflicts_with_all = &["load", "record"])]
+    replay: Option<PathBuf>,
+    /// Wait for ENTER before each command of a replay
+    #[clap(long, requires = "replay")]
+    step: bool,
 }
 
-fn get_game_config() -> StResult<TheGameDefs> {
-    let the_game_defs = TheGameDefsBuilder::default()
-        .build()
-        .map_err(StarTrustError::GeneralError)?;
+fn get_game_config(get_opts: &GetOpts) -> StResult<TheGameDefs> {
+    let mut game_config = match &get_opts.config {
+        Some(path) => GameConfig::from_file(path)?,
+        None => GameConfig::default(),
+    };
+    game_config.override_with(&GameConfig {
+        difficulty: get_opts.difficulty,
+        energy: get_opts.energy,
+        torpedoes: get_opts.torpedoes,
+        years: get_opts.years,
+        seed: get_opts.seed,
+        ..GameConfig::default()
+    });
+    let mut builder = TheGameDefsBuilder::default();
+    game_config.apply(&mut builder);
+    if let Some(random_mode) = get_opts.rng {
+        builder.random_mode(random_mode);
+    }
+    let the_game_defs = builder.build().map_err(StarTrustError::ConfigError)?;
     Ok(the_game_defs)
 }
 
+/// The transcript file for the `n`th game of the session, counting from 1
+fn transcript_path(path: &Path, n: usize) -> PathBuf {
+    if n == 1 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, n, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, n),
+    };
+    path.with_file_name(file_name)
+}
+
+/// Replay a recorded game, optionally one command at a time
+fn replay<W: WriteColor>(path: &Path, step: bool, sout: &mut W) -> StResult<()> {
+    let transcript = Transcript::read_from(BufReader::new(File::open(path)?))?;
+    let mut the_game = transcript.new_game();
+    let mut replayer = transcript.replayer();
+    let sin = stdin();
+    let mut sin = sin.lock();
+
+    clrscr(sout)?;
+    show_title(sout)?;
+    let result = the_game.play_stepwise(&mut replayer, sout, |sout| {
+        if step {
+            write!(sout, "[PRESS ENTER FOR THE NEXT COMMAND]")?;
+            sout.flush()?;
+            let mut line = String::new();
+            sin.read_line(&mut line)?;
+        }
+        Ok(())
+    });
+    match result {
+        Err(StarTrustError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+            writeln!(sout, "\n\nEND OF TRANSCRIPT.")?;
+        }
+        result => result?,
+    }
+    writeln!(sout, "(GAME SEED WAS {})", the_game.seed())?;
+    sout.reset()?;
+    Ok(())
+}
+
 fn init_logger(get_opts: &GetOpts) {
     let mut builder = pretty_env_logger::formatted_builder();
     if let Ok(s) = ::std::env::var("RUST_LOG") {
@@ -59,6 +152,7 @@ fn init_logger(get_opts: &GetOpts) {
 fn main() -> Result<(), StarTrustError> {
     let get_opts = GetOpts::parse();
     init_logger(&get_opts);
+    let the_game_config = get_game_config(&get_opts)?;
     let sin = stdin();
     let choice = if !atty::is(atty::Stream::Stdout) || get_opts.no_color {
         ColorChoice::Never
@@ -68,22 +162,37 @@ fn main() -> Result<(), StarTrustError> {
         ColorChoice::Auto
     };
     let mut sout = StandardStream::stdout(choice);
+    if let Some(path) = &get_opts.replay {
+        return replay(path, get_opts.step, &mut sout);
+    }
     show_title(&mut sout)?;
     show_instructions(&mut sin.lock(), &mut sout)?;
 
-    let the_game_config = get_game_config()?;
+    let mut saved_game = match &get_opts.load {
+        Some(path) => Some(TheGame::load(BufReader::new(File::open(path)?))?),
+        None => None,
+    };
 
-    loop {
-        let mut the_game = TheGame::new(&the_game_config);
+    for game_number in 1.. {
+        let mut the_game = saved_game
+            .take()
+            .unwrap_or_else(|| TheGame::new(&the_game_config));
 
         debug!("About to print title");
 
         clrscr(&mut sout)?;
         show_title(&mut sout)?;
 
-        let _game = the_game.play(&mut sin.lock(), &mut sout)?;
+        let mut recorder = Recorder::new(sin.lock());
+        the_game.play(&mut recorder, &mut sout)?;
+        writeln!(sout, "(GAME SEED WAS {})", the_game.seed())?;
+        if let Some(path) = &get_opts.record {
+            let path = transcript_path(path, game_number);
+            Transcript::new(&the_game, recorder.recorded()).write_to(File::create(&path)?)?;
+            writeln!(sout, "(TRANSCRIPT SAVED TO {})", path.display())?;
+        }
 
-        let _ = write!(sout, "\nTRY AGAIN? ")?;
+        write!(sout, "\nTRY AGAIN? ")?;
         sout.flush()?;
         let ans = yesno(&mut sin.lock())?;
         if ans != 'Y' {
@@ -92,4 +201,5 @@ fn main() -> Result<(), StarTrustError> {
             return Ok(());
         }
     }
+    Ok(())
 } /* End main */
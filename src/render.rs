@@ -0,0 +1,725 @@
+//! # startrust::render
+//!
+//! Show the events of the game on a terminal, the way the original game printed them
+
+use termcolor::{ColorSpec, WriteColor};
+
+use crate::interaction::{beep, delay};
+use crate::the_game::{Component, Ending, GameEvent, Sector, ShortRangeScan, Weapon};
+use crate::StResult;
+
+/// The number of lines of status beside the short range scan
+const STATUS_LINES: usize = 9;
+
+/// Write a batch of game events to the terminal
+pub fn render_events<W: WriteColor>(sout: &mut W, events: &[GameEvent]) -> StResult<()> {
+    for event in events {
+        render_event(sout, event)?;
+    }
+    sout.flush()?;
+    Ok(())
+}
+
+fn render_event<W: WriteColor>(sout: &mut W, event: &GameEvent) -> StResult<()> {
+    match event {
+        GameEvent::GameStarted {
+            klingons,
+            years,
+            starbases,
+        } => {
+            writeln!(
+                sout,
+                "OBJECTIVE: DESTROY {} KLINGON BATTLE CRUISERS IN {} YEARS.",
+                klingons, years
+            )?;
+            writeln!(sout, " THE NUMBER OF STARBASES IS {}.\n", starbases)?;
+        }
+        GameEvent::QuadrantEntered { .. } | GameEvent::Docked => {}
+        GameEvent::MovedWithinQuadrant { .. } => writeln!(sout)?,
+        GameEvent::Blocked { by, sector } => {
+            writeln!(sout)?;
+            writeln!(
+                sout,
+                "BLOCKED BY {} AT SECTOR {} - {}",
+                by.as_ref(),
+                sector.row() + 1,
+                sector.column() + 1
+            )?;
+        }
+        GameEvent::StarbaseProtects => writeln!(sout, "STARBASE PROTECTS ENTERPRISE.")?,
+        GameEvent::EnterpriseHit {
+            from,
+            hit,
+            energy_left,
+            shields_left,
+            ..
+        } => {
+            show_hit(sout, "ENTERPRISE FROM", *from, *hit, *energy_left)?;
+            if let Some(shields_left) = shields_left {
+                writeln!(sout, "    SHIELDS DOWN TO {:.3}", shields_left)?;
+            }
+        }
+        GameEvent::EnterpriseHitByRomulan {
+            from,
+            hit,
+            energy_left,
+            shields_left,
+        } => {
+            show_hit(
+                sout,
+                "ENTERPRISE FROM ROMULAN AT",
+                *from,
+                *hit,
+                *energy_left,
+            )?;
+            if let Some(shields_left) = shields_left {
+                writeln!(sout, "    SHIELDS DOWN TO {:.3}", shields_left)?;
+            }
+        }
+        GameEvent::RomulanDestroyed { provoked, .. } => {
+            write!(sout, "\nROMULAN DESTROYED!\n")?;
+            if !provoked {
+                writeln!(sout, "STARFLEET WILL HEAR OF THIS UNPROVOKED ATTACK.")?;
+            }
+        }
+        GameEvent::RomulansProvoked { romulans } => {
+            if *romulans == 1 {
+                writeln!(sout, "THE ROMULAN DROPS ITS CLOAK AND OPENS FIRE!")?
+            } else {
+                writeln!(
+                    sout,
+                    "THE {} ROMULANS DROP THEIR CLOAKS AND OPEN FIRE!",
+                    romulans
+                )?
+            }
+        }
+        GameEvent::RomulansCloaked { cloaked } => {
+            if *cloaked {
+                writeln!(sout, "ROMULAN SHIPS FADE FROM VIEW.")?
+            } else {
+                writeln!(sout, "ROMULAN SHIPS DECLOAK NEARBY.")?
+            }
+        }
+        GameEvent::KlingonHit {
+            sector,
+            hit,
+            shields_left,
+            ..
+        } => show_hit(sout, "KLINGON AT", *sector, *hit, *shields_left)?,
+        GameEvent::KlingonDestroyed { weapon, .. } => match weapon {
+            Weapon::Phasers => writeln!(sout, "**KLINGON DESTROYED**")?,
+            Weapon::PhotonTorpedo => write!(sout, "\nKLINGON DESTROYED!\n")?,
+            Weapon::Nova => writeln!(sout, "**KLINGON DESTROYED BY THE NOVA**")?,
+        },
+        GameEvent::TorpedoFired => write!(sout, "TRACK: ")?,
+        GameEvent::TorpedoTrack { sector } => {
+            write!(sout, "{} - {}  ", sector.row() + 1, sector.column() + 1)?
+        }
+        GameEvent::TorpedoMissed => writeln!(sout, "MISSED!")?,
+        GameEvent::Nova { sector } => write!(
+            sout,
+            "\nSTAR AT SECTOR {} - {} GOES NOVA!\n",
+            sector.row() + 1,
+            sector.column() + 1
+        )?,
+        GameEvent::KlingonDisplaced { from, to, .. } => writeln!(
+            sout,
+            "KLINGON AT SECTOR {} - {} PUSHED TO SECTOR {} - {}",
+            from.row() + 1,
+            from.column() + 1,
+            to.row() + 1,
+            to.column() + 1
+        )?,
+        GameEvent::EnterpriseBuffeted {
+            hit,
+            energy_left,
+            shields_left,
+        } => {
+            writeln!(
+                sout,
+                "{:.3} UNIT NOVA BLAST ON ENTERPRISE ({:.3} LEFT)",
+                hit, energy_left
+            )?;
+            if let Some(shields_left) = shields_left {
+                writeln!(sout, "    SHIELDS DOWN TO {:.3}", shields_left)?;
+            }
+        }
+        GameEvent::EnterpriseDisplaced { sector } => writeln!(
+            sout,
+            "ENTERPRISE PUSHED TO SECTOR {} - {}",
+            sector.row() + 1,
+            sector.column() + 1
+        )?,
+        GameEvent::Supernova {
+            quadrant,
+            klingons,
+            starbases,
+        } => {
+            beep();
+            write!(
+                sout,
+                "\n***SUPERNOVA*** QUADRANT {} - {} IS DESTROYED",
+                quadrant.row() + 1,
+                quadrant.column() + 1
+            )?;
+            writeln!(
+                sout,
+                ", WITH {} KLINGONS AND {} STARBASES",
+                klingons, starbases
+            )?;
+            beep();
+        }
+        GameEvent::ThrownClear {
+            quadrant,
+            hit,
+            energy_left,
+        } => writeln!(
+            sout,
+            "SHOCK WAVE ({:.3} UNITS) THROWS THE ENTERPRISE INTO QUADRANT {} - {} ({:.3} LEFT)",
+            hit,
+            quadrant.row() + 1,
+            quadrant.column() + 1,
+            energy_left
+        )?,
+        GameEvent::WarpDiverted { quadrant } => writeln!(
+            sout,
+            "SUPERNOVA IN QUADRANT {} - {}! COMPUTER DROPS THE ENTERPRISE OUT OF WARP",
+            quadrant.row() + 1,
+            quadrant.column() + 1
+        )?,
+        GameEvent::EnteredSupernova { quadrant } => {
+            beep();
+            writeln!(
+                sout,
+                "\nWITH THE COMPUTER DOWN, THE ENTERPRISE WARPS INTO THE SUPERNOVA IN QUADRANT {} - {}",
+                quadrant.row() + 1,
+                quadrant.column() + 1
+            )?;
+        }
+        GameEvent::StarbaseUnderSiege { quadrant, deadline } => {
+            beep();
+            writeln!(sout, "\nSUBSPACE DISTRESS CALL:")?;
+            writeln!(
+                sout,
+                "  STARBASE IN QUADRANT {} - {} UNDER KLINGON ATTACK.",
+                quadrant.row() + 1,
+                quadrant.column() + 1
+            )?;
+            writeln!(sout, "  IT CAN HOLD OUT UNTIL STARDATE {}.", deadline)?;
+        }
+        GameEvent::SiegeRelieved { quadrant } => writeln!(
+            sout,
+            "THE STARBASE IN QUADRANT {} - {} THANKS YOU FOR ITS RESCUE.",
+            quadrant.row() + 1,
+            quadrant.column() + 1
+        )?,
+        GameEvent::StarbaseLost { quadrant } => {
+            beep();
+            writeln!(
+                sout,
+                "\nSTARFLEET REPORTS THE STARBASE IN QUADRANT {} - {} HAS BEEN DESTROYED!",
+                quadrant.row() + 1,
+                quadrant.column() + 1
+            )?;
+        }
+        GameEvent::StarbaseDestroyed { .. } => {
+            write!(sout, "\nSTARBASE DESTROYED! . . . GOOD WORK!\n")?
+        }
+        GameEvent::KlingonMoved { from, to, .. } => writeln!(
+            sout,
+            "KLINGON AT SECTOR {} - {} MOVES TO SECTOR {} - {}",
+            from.row() + 1,
+            from.column() + 1,
+            to.row() + 1,
+            to.column() + 1
+        )?,
+        GameEvent::KlingonFled { sector, to, .. } => writeln!(
+            sout,
+            "KLINGON AT SECTOR {} - {} ESCAPES TO QUADRANT {} - {}",
+            sector.row() + 1,
+            sector.column() + 1,
+            to.row() + 1,
+            to.column() + 1
+        )?,
+        GameEvent::SpaceStorm {
+            component,
+            repair_time,
+        } => {
+            beep();
+            writeln!(sout, "**SPACE STORM, {} DAMAGED**", component)?;
+            show_est_repair_time(sout, *repair_time)?;
+            delay(100);
+            beep();
+        }
+        GameEvent::SpockRepair { .. } => writeln!(sout, "**SPOCK USED A NEW REPAIR TECHNIQUE**")?,
+        GameEvent::RepairCompleted { component } => {
+            writeln!(sout, "{} ARE FIXED!", component.as_ref())?;
+            beep();
+        }
+        GameEvent::HitDamage {
+            component,
+            repair_time,
+        } => {
+            beep();
+            writeln!(sout, "**HIT DAMAGED {}**", component)?;
+            show_est_repair_time(sout, *repair_time)?;
+        }
+        GameEvent::ComponentDamaged {
+            component,
+            repair_time,
+        } => {
+            write!(sout, "{} DAMAGED.  ", component.as_ref())?;
+            beep();
+            show_est_repair_time(sout, *repair_time)?;
+        }
+        GameEvent::WarpLimited {
+            max_warp,
+            repair_time,
+        } => {
+            beep();
+            write!(
+                sout,
+                "{} DAMAGED; MAX IS {}; ",
+                Component::WarpEngines.as_ref(),
+                max_warp
+            )?;
+            show_est_repair_time(sout, *repair_time)?;
+            beep();
+        }
+        GameEvent::TubesBlocked { repair_time } => {
+            write!(sout, "SPACE CRUD BLOCKING TUBES.  ")?;
+            show_est_repair_time(sout, *repair_time)?;
+            beep();
+        }
+        GameEvent::NoTorpedoesLeft => {
+            writeln!(sout, "NO TORPEDOES LEFT!")?;
+            beep();
+        }
+        GameEvent::NoProbesLeft => {
+            writeln!(sout, "NO PROBES LEFT!")?;
+            beep();
+        }
+        GameEvent::ProbeLaunched { probes_left } => {
+            writeln!(sout, "PROBE LAUNCHED, {} LEFT", probes_left)?
+        }
+        GameEvent::ProbeReport { quadrant, contents } => {
+            write!(
+                sout,
+                "PROBE REPORTS QUADRANT {} - {}:  ",
+                quadrant.row() + 1,
+                quadrant.column() + 1
+            )?;
+            contents.draw(sout, false)?;
+            writeln!(sout)?;
+        }
+        GameEvent::ProbeLost => writeln!(sout, "PROBE HAS LEFT THE GALAXY\n")?,
+        GameEvent::PlanetHit { .. } => {
+            write!(sout, "\nTORPEDO BURNS UP IN THE PLANET'S ATMOSPHERE\n")?
+        }
+        GameEvent::TorpedoSwallowed { .. } => {
+            write!(sout, "\nTORPEDO SWALLOWED BY A BLACK HOLE\n")?
+        }
+        GameEvent::TorpedoLostInNebula { .. } => write!(sout, "\nTORPEDO LOST IN THE NEBULA\n")?,
+        GameEvent::SwallowedByBlackHole { sector } => {
+            beep();
+            writeln!(
+                sout,
+                "\nTHE ENTERPRISE IS CAUGHT BY THE BLACK HOLE AT SECTOR {} - {}!",
+                sector.row() + 1,
+                sector.column() + 1
+            )?;
+            beep();
+        }
+        GameEvent::PhaserOutOfRange { sector, .. } => writeln!(
+            sout,
+            "NEBULA SCATTERS PHASER FIRE AT KLINGON AT SECTOR {} - {}",
+            sector.row() + 1,
+            sector.column() + 1
+        )?,
+        GameEvent::NoPlanetNearby => {
+            writeln!(sout, "NO PLANET WITHIN ONE SECTOR TO ORBIT")?;
+            beep();
+        }
+        GameEvent::EnteredOrbit { class } => writeln!(
+            sout,
+            "ENTERING STANDARD ORBIT AROUND CLASS {} PLANET",
+            class.as_ref()
+        )?,
+        GameEvent::NotInOrbit => {
+            writeln!(sout, "THE ENTERPRISE IS NOT IN ORBIT")?;
+            beep();
+        }
+        GameEvent::TransporterBlockedByShields => {
+            writeln!(sout, "CAN'T BEAM THROUGH THE SHIELDS, CAPTAIN")?;
+            beep();
+        }
+        GameEvent::PartyLanded { by } => writeln!(sout, "LANDING PARTY DOWN BY {}", by.as_ref())?,
+        GameEvent::PartyReturned { by, crystals } => {
+            writeln!(sout, "LANDING PARTY BACK ABOARD BY {}", by.as_ref())?;
+            if *crystals {
+                writeln!(sout, "DILITHIUM CRYSTALS STOWED IN THE HOLD")?;
+            }
+        }
+        GameEvent::PartyNotAshore => {
+            writeln!(sout, "THE LANDING PARTY IS STILL ABOARD")?;
+            beep();
+        }
+        GameEvent::PartyAshore => {
+            writeln!(sout, "THE LANDING PARTY IS STILL ON THE PLANET!")?;
+            beep();
+        }
+        GameEvent::PartyLost => writeln!(sout, "THE LANDING PARTY WAS LOST WITH THE PLANET")?,
+        GameEvent::CrystalsMined => writeln!(sout, "LANDING PARTY HAS MINED DILITHIUM CRYSTALS")?,
+        GameEvent::NoDilithiumFound => writeln!(sout, "NO DILITHIUM FOUND ON THIS PLANET")?,
+        GameEvent::NoCrystals => {
+            writeln!(sout, "NO DILITHIUM CRYSTALS ABOARD")?;
+            beep();
+        }
+        GameEvent::CrystalsUsed { energy } => {
+            writeln!(sout, "THE CRYSTALS TAKE!  ENERGY BOOSTED TO {:.3}", energy)?
+        }
+        GameEvent::CrystalsFailed { repair_time } => {
+            beep();
+            writeln!(sout, "**THE CRYSTALS FRACTURE, WARP ENGINES DAMAGED**")?;
+            show_est_repair_time(sout, *repair_time)?;
+            beep();
+        }
+        GameEvent::NotEnoughTorpedoes { torpedoes } => {
+            writeln!(sout, "ONLY {} TORPEDOES LEFT!", torpedoes)?;
+            beep();
+        }
+        GameEvent::NotEnoughEnergy { energy } => {
+            writeln!(sout, "ONLY GOT {:03}", energy)?; // The printf format was "%.3f"
+        }
+        GameEvent::NotEnoughShieldEnergy { shields } => {
+            writeln!(sout, "SHIELDS ONLY HAVE {:.3}", shields)?;
+        }
+        GameEvent::ShieldsSet {
+            up,
+            shields,
+            energy,
+        } => writeln!(
+            sout,
+            "SHIELDS {} AT {:.3}, {:.3} LEFT IN MAIN RESERVES",
+            if *up { "UP" } else { "DOWN" },
+            shields,
+            energy
+        )?,
+        GameEvent::InvalidOrder => beep(),
+        GameEvent::ShortRangeScan(scan) => draw_s_range_scan(sout, scan)?,
+        GameEvent::LongRangeScan {
+            quadrant,
+            quadrants,
+        } => {
+            writeln!(
+                sout,
+                "{} FOR QUADRANT {} - {}",
+                Component::LongRangeSensors.as_ref(),
+                quadrant.0,
+                quadrant.1
+            )?;
+            for (i, row) in quadrants.iter().enumerate() {
+                for (j, quadrant_contents) in row.iter().enumerate() {
+                    // Drawn contents start with a space, or an R for Romulans
+                    write!(sout, "  ")?;
+                    match quadrant_contents {
+                        Some(quadrant_contents) => {
+                            quadrant_contents.draw(sout, i == 1 && j == 1)?
+                        }
+                        None => {
+                            write!(sout, " ")?;
+                            draw_unknown(sout)?
+                        }
+                    }
+                }
+                writeln!(sout)?;
+            }
+        }
+        GameEvent::GalacticRecords {
+            stardate,
+            quadrants,
+            current,
+        } => {
+            writeln!(sout, "CUMULATIVE GALACTIC MAP FOR STARDATE {}", stardate)?;
+            for (i, row) in quadrants.iter().enumerate() {
+                for (j, quadrant_contents) in row.iter().enumerate() {
+                    write!(sout, " ")?;
+                    quadrant_contents.draw(sout, (i, j) == *current)?;
+                }
+                writeln!(sout)?;
+            }
+        }
+        GameEvent::DamageReport { components } => {
+            writeln!(sout, "DAMAGE CONTROL REPORT:")?;
+            for status in components {
+                write!(sout, "  {:<24}", status.component.as_ref())?;
+                if status.repair_time <= 0 {
+                    writeln!(sout, "OPERATIONAL")?;
+                } else if status.repairs_done > 0 {
+                    writeln!(
+                        sout,
+                        "{} YEARS TO REPAIR ({} DONE)",
+                        status.repair_time, status.repairs_done
+                    )?;
+                } else {
+                    writeln!(sout, "{} YEARS TO REPAIR", status.repair_time)?;
+                }
+            }
+            if components.iter().any(|status| status.repair_time > 0) {
+                writeln!(
+                    sout,
+                    "REPAIRS PROGRESS ONE YEAR FOR EACH STARDATE UNDER WAY."
+                )?;
+                writeln!(sout, "DOCKING AT A STARBASE WILL REPAIR EVERYTHING.")?;
+            }
+            writeln!(sout)?;
+        }
+        GameEvent::TorpedoData { targets } => {
+            if targets.is_empty() {
+                writeln!(sout, "NO KLINGONS IN THIS QUADRANT.")?;
+            }
+            for target in targets {
+                writeln!(
+                    sout,
+                    "KLINGON AT SECTOR {} - {}: COURSE = {:.2}, DISTANCE = {:.2}",
+                    target.sector.row() + 1,
+                    target.sector.column() + 1,
+                    target.course,
+                    target.distance
+                )?;
+            }
+            writeln!(sout)?;
+        }
+        GameEvent::CourseCalculated { course, distance } => {
+            writeln!(sout, "COURSE = {:.2}, DISTANCE = {:.2}\n", course, distance)?
+        }
+        GameEvent::StarbaseCourse {
+            quadrant,
+            course,
+            warp,
+        } => writeln!(
+            sout,
+            "NEAREST KNOWN STARBASE IS IN QUADRANT {} - {}: COURSE = {:.2}, WARP = {:.2}\n",
+            quadrant.row() + 1,
+            quadrant.column() + 1,
+            course,
+            warp
+        )?,
+        GameEvent::NoKnownStarbase => {
+            writeln!(sout, "NO STARBASES ARE SHOWN IN THE GALACTIC RECORDS.\n")?
+        }
+        GameEvent::StatusReport {
+            klingons_left,
+            starbases,
+            years_left,
+        } => {
+            writeln!(sout, "STATUS REPORT:")?;
+            writeln!(sout, "  {} KLINGONS LEFT", klingons_left)?;
+            writeln!(sout, "  {} STARBASES", starbases)?;
+            writeln!(sout, "  {} YEARS LEFT\n", years_left)?;
+        }
+        GameEvent::GameOver {
+            stardate,
+            ending,
+            starbases_lost,
+        } => {
+            write!(sout, "\nIT IS STARDATE {}.\n", stardate)?;
+            if !starbases_lost.is_empty() {
+                writeln!(sout, "STARBASES LOST TO THE KLINGONS:")?;
+                for quadrant in starbases_lost {
+                    writeln!(
+                        sout,
+                        "  QUADRANT {} - {}",
+                        quadrant.row() + 1,
+                        quadrant.column() + 1
+                    )?;
+                }
+            }
+            show_ending(sout, ending)?;
+        }
+    }
+    sout.flush()?;
+    Ok(())
+}
+
+/// Show hit on Enterprise or Klingon
+fn show_hit<W: WriteColor>(sout: &mut W, es: &str, sector: Sector, h: f64, n: f64) -> StResult<()> {
+    writeln!(
+        sout,
+        "{:.3} UNIT HIT ON {} SECTOR {} - {}  ({:.3} LEFT)",
+        h,
+        es,
+        sector.row() + 1,
+        sector.column() + 1,
+        n
+    )?;
+    Ok(())
+} /* End showhit */
+
+/// Show estimated time for repair
+fn show_est_repair_time<W: WriteColor>(sout: &mut W, repair_time: i32) -> StResult<()> {
+    write!(sout, "{} YEARS ESTIMATED FOR REPAIR.\n\n", repair_time)?;
+    Ok(())
+} /* End showestreptime */
+
+/// Tell the player how it all turned out
+fn show_ending<W: WriteColor>(sout: &mut W, ending: &Ending) -> StResult<()> {
+    let klingons_left = match ending {
+        Ending::Won {
+            klingons_destroyed,
+            years,
+            rating,
+            unprovoked_romulan_kills,
+            supernova_kills,
+        } => {
+            writeln!(sout, "THE FEDERATION HAS BEEN SAVED!")?;
+            writeln!(sout, "YOU ARE PROMOTED TO ADMIRAL.")?;
+            if *unprovoked_romulan_kills > 0 {
+                writeln!(
+                    sout,
+                    "BUT THE {} ROMULANS YOU DESTROYED UNPROVOKED COUNT AGAINST YOU.",
+                    unprovoked_romulan_kills
+                )?;
+            }
+            if *supernova_kills > 0 {
+                writeln!(
+                    sout,
+                    "{} OF THE KLINGONS WERE LOST TO SUPERNOVAS, AND COUNT FOR LESS.",
+                    supernova_kills
+                )?;
+            }
+            write!(
+                sout,
+                "{} KLINGONS IN {} YEARS.  RATING = {}\n\n",
+                klingons_destroyed, years, rating
+            )?;
+            return Ok(());
+        }
+        Ending::Quit => {
+            writeln!(sout, "OKAY, QUITTER -- NO KUDOS FOR YOU.")?;
+            return Ok(());
+        }
+        Ending::RanOutOfTime { klingons_left } => {
+            writeln!(sout, "YOU RAN OUT OF TIME!")?;
+            klingons_left
+        }
+        Ending::RanOutOfEnergy { klingons_left } => {
+            writeln!(sout, "YOU RAN OUT OF ENERGY!")?;
+            klingons_left
+        }
+        Ending::SwallowedByBlackHole { klingons_left } => {
+            writeln!(sout, "THE ENTERPRISE WAS LOST IN A BLACK HOLE!")?;
+            klingons_left
+        }
+        Ending::CaughtInSupernova { klingons_left } => {
+            writeln!(sout, "THE ENTERPRISE WAS DESTROYED IN THE SUPERNOVA!")?;
+            klingons_left
+        }
+    };
+    writeln!(sout, "THANKS TO YOUR BUNGLING, THE FEDERATION WILL BE")?;
+    writeln!(
+        sout,
+        "CONQUERED BY THE REMAINING {} KLINGON CRUISERS!",
+        klingons_left
+    )?;
+    writeln!(sout, "YOU ARE DEMOTED TO CABIN BOY!")?;
+    Ok(())
+}
+
+/// Draw a quadrant that can't be seen
+fn draw_unknown<W: WriteColor>(sout: &mut W) -> StResult<()> {
+    sout.set_color(ColorSpec::new().set_dimmed(true))?;
+    write!(sout, "***")?;
+    sout.flush()?;
+    sout.reset()?;
+    Ok(())
+}
+
+/// Draw the short range scan, with the ship's status alongside it. The status takes nine lines,
+/// so it runs on past the bottom of a smaller quadrant.
+fn draw_s_range_scan<W: WriteColor>(sout: &mut W, scan: &ShortRangeScan) -> StResult<()> {
+    let width = scan.sectors.first().map_or(0, Vec::len);
+    for i in 0..scan.sectors.len().max(STATUS_LINES) {
+        match scan.sectors.get(i) {
+            Some(row) => {
+                for sector_contents in row {
+                    write!(sout, "{} ", sector_contents.to_char())?;
+                }
+            }
+            None => write!(sout, "{:1$}", "", 2 * width)?,
+        }
+        if i >= STATUS_LINES {
+            writeln!(sout)?;
+            continue;
+        }
+        write!(sout, "  ")?;
+        match i {
+            0 => {
+                writeln!(sout, "YEARS = {}", scan.years_left)?;
+            }
+            1 => {
+                writeln!(sout, "STARDATE = {}", scan.stardate)?;
+            }
+            2 => {
+                write!(sout, "CONDITION: ")?;
+                sout.set_color(&scan.condition.get_color_spec())?;
+                writeln!(sout, "{}", scan.condition.as_ref())?;
+                sout.reset()?;
+            }
+            3 => {
+                writeln!(sout, "QUADRANT = {} - {}", scan.quadrant.0, scan.quadrant.1)?;
+            }
+            4 => {
+                writeln!(sout, "SECTOR = {} - {}", scan.sector.0, scan.sector.1)?;
+            }
+            5 => {
+                writeln!(sout, "ENERGY = {:03}", scan.energy)?; // printf format string was "%.3f"
+            }
+            6 => {
+                writeln!(
+                    sout,
+                    "{} = {}",
+                    Component::PhotonTorpedoes.as_ref(),
+                    scan.photon_torpedoes
+                )?;
+            }
+            7 => {
+                writeln!(sout, "KLINGONS LEFT = {}", scan.klingons_left)?;
+            }
+            8 => {
+                writeln!(
+                    sout,
+                    "SHIELDS = {:.3} ({})",
+                    scan.shields,
+                    if scan.shields_up { "UP" } else { "DOWN" }
+                )?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use termcolor::NoColor;
+
+    use crate::{StResult, TheGame, TheGameDefsBuilder};
+
+    /// What the player types in the golden game
+    const GOLDEN_INPUT: &str = include_str!("testdata/golden_game_input.txt");
+    /// What the terminal shows for it
+    const GOLDEN_OUTPUT: &str = include_str!("testdata/golden_game_output.txt");
+
+    /// Any change to what the player sees shows up here, and has to be made on purpose
+    #[test]
+    fn test_play_matches_golden_output() -> StResult<()> {
+        let the_game_defs = TheGameDefsBuilder::default().seed(7).build().unwrap();
+        let mut the_game = TheGame::new(&the_game_defs);
+        let mut played = NoColor::new(Vec::new());
+        the_game.play(&mut GOLDEN_INPUT.as_bytes(), &mut played)?;
+        let played = String::from_utf8(played.into_inner()).unwrap();
+        assert_eq!(GOLDEN_OUTPUT, played);
+        Ok(())
+    }
+}
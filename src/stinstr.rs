@@ -52,6 +52,33 @@ TO THE RIGHT.  DOCKING AT A STARBASE IS DONE BY OCCUPYING
 AN ADJACENT SECTOR, AND REPROVISIONS YOUR STARSHIP WITH
 ENERGY AND PHOTON TORPEDOES, AS WELL AS REPAIRING ALL DAMAGES.
 
+   ROMULANS (R) ALSO ROAM THE GALAXY.  THEY ARE NEUTRAL, AND
+OFTEN CLOAKED SO THAT NO SCAN CAN SEE THEM, BUT IF YOU RAM OR
+TORPEDO ONE, EVERY ROMULAN IN THE QUADRANT WILL FIGHT YOU
+UNTIL YOU LEAVE.  YOU DON'T NEED TO DESTROY THEM TO WIN, AND
+DESTROYING ONE UNPROVOKED WILL COST YOU IN YOUR RATING.  THE
+SCANS MARK QUADRANTS WITH ROMULANS IN SIGHT WITH AN R.
+
+   SOME QUADRANTS HAVE A PLANET (@).  A LANDING PARTY CAN
+MINE DILITHIUM CRYSTALS ON SOME OF THEM, AND THE CRYSTALS
+CAN BOOST YOUR ENERGY WHEN NO STARBASE IS NEAR.
+
+   BEWARE OF BLACK HOLES (O), WHICH SWALLOW TORPEDOES AND
+MAY PULL IN A SHIP THAT PASSES NEXT TO THEM, AND NEBULAE (#),
+WHICH HIDE THEIR QUADRANT FROM THE LONG RANGE SENSORS AND
+SCATTER PHASER FIRE AIMED MORE THAN 4 SECTORS AWAY.
+
+   NOW AND THEN A STAR ELSEWHERE IN THE GALAXY GOES
+SUPERNOVA, DESTROYING EVERY KLINGON AND STARBASE IN ITS
+QUADRANT.  THE GALACTIC RECORDS MARK SUCH QUADRANTS *S*, AND
+FOR 5 YEARS THE SHIP'S COMPUTER WILL DROP YOU OUT OF WARP
+RATHER THAN LET YOU ENTER ONE -- UNLESS IT IS DAMAGED.
+KLINGONS LOST TO SUPERNOVAS COUNT FOR LESS IN YOUR RATING.
+
+   KLINGONS WILL ALSO BESIEGE STARBASES.  A SUBSPACE DISTRESS
+CALL TELLS YOU WHICH ONE, AND THE STARDATE IT CAN HOLD OUT
+UNTIL.  REACH ITS QUADRANT BY THEN OR THE STARBASE IS LOST.
+
    YOUR STARSHIP WILL ACT ON THE FOLLOWING COMMANDS:
 COMMAND 1 - WARP ENGINE CONTROL IS USED TO MOVE THE ENTERPRISE.
             YOU WILL BE ASKED TO SET THE DISTANCE (MEASURED
@@ -105,22 +132,90 @@ COMMAND 3 - THE LONG RANGE SENSOR SCAN SUMMARIZES THE QUADRANT
 COMMAND 4 - FIRE PHASERS; THE PORTION OF THE ENTERPRISE'S
             ENERGY THAT YOU SPECIFY WILL BE DIVIDED EVENLY 
             AMONG THE KLINGONS IN THE QUADRANT AND FIRED
-            AT THEM.  SURVIVING KLINGONS WILL RETALIATE.
+            AT THEM.  WITH MORE THAN ONE KLINGON, YOU MAY
+            INSTEAD DIVIDE IT BY DISTANCE, SO THAT THE
+            NEARER ONES GET MORE, OR TARGET MANUALLY BY
+            GIVING EACH KLINGON ITS OWN AMOUNT.  SURVIVING
+            KLINGONS WILL RETALIATE.
             PHASER FIRE BYPASSES STARS AND STARBASES, BUT
             IS ATTENUATED BY THE DISTANCE IT TRAVELS.
             THE ARRIVING ENERGY DEPLETES THE SHIELD POWER
-            OF ITS TARGET.  ENERGY IS AUTOMATICALLY DIVERTED
-            TO THE SHIELDS AS NEEDED, BUT IF YOU RUN OUT
+            OF ITS TARGET.  KLINGON HITS DRAIN YOUR SHIELDS
+            FIRST (SEE COMMAND 8), BUT WHAT GETS PAST THEM
+            COMES OUT OF YOUR ENERGY, AND IF YOU RUN OUT
             OF ENERGY YOU'LL GET FRIED.
 
 COMMAND 5 - PHOTON TORPEDO CONTROL WILL LAUNCH A TORPEDO
             ON A COURSE YOU SPECIFY WHICH WILL DESTROY
             ANY OBJECT IN ITS PATH.  RANGE IS LIMITED TO
-            THE LOCAL QUADRANT.  EXPECT RETURN FIRE FROM
-            SURVIVING KLINGONS.
+            THE LOCAL QUADRANT.  YOU MAY FIRE A SPREAD OF
+            UP TO 3 TORPEDOES, EACH ON ITS OWN COURSE OR
+            FANNED OUT AROUND ONE COURSE BY AN ANGLE YOU
+            GIVE (IN COURSE UNITS).  EXPECT RETURN FIRE
+            FROM SURVIVING KLINGONS ONCE THE TORPEDOES
+            HAVE GONE.  A STAR HIT BY A TORPEDO GOES
+            NOVA, BATTERING AND PUSHING AWAY ANY SHIP NEXT
+            TO IT AND SETTING OFF NEIGHBOURING STARS.  NOW
+            AND THEN ONE GOES SUPERNOVA, DESTROYING THE
+            WHOLE QUADRANT AND THROWING YOU CLEAR.
 
 COMMAND 6 - THE GALACTIC RECORDS SECTION OF THE SHIP'S
             COMPUTER RESPONDS TO THIS COMMAND BY PRINTING
             OUT A GALACTIC MAP SHOWING THE RESULTS OF ALL
             PREVIOUS SENSOR SCANS.
+
+COMMAND 7 - SAVES THE GAME TO THE FILE YOU NAME.  START
+            STARTRUST WITH --load AND THE FILE NAME TO
+            PICK UP WHERE YOU LEFT OFF.
+
+COMMAND 8 - SHIELD CONTROL RAISES OR LOWERS THE SHIELDS,
+            AND MOVES ENERGY INTO THEM FROM THE MAIN
+            RESERVES (OR BACK, IF YOU ENTER A NEGATIVE
+            AMOUNT).  RAISED SHIELDS SOAK UP KLINGON HITS
+            UNTIL THEIR ENERGY RUNS OUT.  HITS THAT GET
+            THROUGH MAY DAMAGE THE SHIP, AND ARE TWICE AS
+            LIKELY TO WITH THE SHIELDS DOWN.
+
+COMMAND 9 - THE DAMAGE REPORT LISTS THE STATE OF EVERY
+            SYSTEM AND HOW LONG REPAIRS WILL TAKE.  IT
+            NEEDS THE DAMAGE CONTROL SYSTEMS TO WORK.
+
+COMMAND 10 - THE LIBRARY COMPUTER SHARES THE GALACTIC
+            RECORDS HARDWARE, AND IS OUT WHEN THEY ARE.
+            IT OFFERS:
+              1 = TORPEDO DATA: THE COURSE AND DISTANCE
+                  TO EACH KLINGON IN THE QUADRANT.
+              2 = A CALCULATOR FOR THE COURSE AND
+                  DISTANCE BETWEEN ANY TWO SECTORS OR
+                  QUADRANTS, GIVEN AS ROW,COLUMN.
+              3 = THE COURSE AND WARP TO THE NEAREST
+                  STARBASE IN THE GALACTIC RECORDS.
+              4 = A STATUS REPORT OF THE KLINGONS AND
+                  STARBASES LEFT, AND THE TIME REMAINING.
+
+COMMAND 11 - LAUNCHES A DEEP SPACE PROBE ON A COURSE YOU
+            SPECIFY.  THE PROBE FLIES ACROSS THE GALAXY,
+            REPORTING EVERY QUADRANT IT PASSES THROUGH TO
+            THE GALACTIC RECORDS, UNTIL IT LEAVES THE
+            GALAXY AND IS LOST.  PROBES SEE THROUGH THE
+            LONG RANGE SENSORS, SO THEY CAN'T BE LAUNCHED
+            WHILE THE SENSORS ARE DAMAGED.  YOU START WITH
+            3, AND DOCKING AT A STARBASE RESTOCKS THEM.
+
+COMMAND 12 - PLANET OPERATIONS.  IT OFFERS:
+              1 = STANDARD ORBIT AROUND A PLANET IN A
+                  NEIGHBOURING SECTOR.
+              2 = THE TRANSPORTER, WHICH SENDS THE
+                  LANDING PARTY DOWN FROM ORBIT, OR
+                  BRINGS IT BACK.  IT CAN'T BEAM THROUGH
+                  RAISED SHIELDS.
+              3 = THE SHUTTLE CRAFT, WHICH DOES THE SAME
+                  WITH THE SHIELDS UP, BUT TAKES A TURN.
+              4 = MINE DILITHIUM.  THE LANDING PARTY
+                  MUST BE ON THE PLANET, AND BRING THE
+                  CRYSTALS BACK ABOARD.  THE ENTERPRISE
+                  CAN'T WARP WHILE THE PARTY IS DOWN.
+              5 = USE A LOAD OF CRYSTALS TO BOOST YOUR
+                  ENERGY.  SOMETIMES THE CRYSTALS FRACTURE
+                  AND DAMAGE THE WARP ENGINES INSTEAD.
 ";
@@ -1,14 +1,30 @@
 //! # startrust::the_game
 
-use std::convert::{Infallible, TryFrom};
+use std::convert::Infallible;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 use log::debug;
 use num_enum::{FromPrimitive, IntoPrimitive};
+use serde::{Deserialize, Serialize};
 use strum_macros::AsRefStr;
 
-#[derive(AsRefStr, Copy, Clone, Debug, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
+use crate::the_game::computer::ComputerRequest;
+use crate::the_game::phasers::PhaserTargeting;
+use crate::the_game::planets::PlanetOrder;
+
+#[derive(
+    AsRefStr,
+    Copy,
+    Clone,
+    Debug,
+    Deserialize,
+    IntoPrimitive,
+    FromPrimitive,
+    Eq,
+    PartialEq,
+    Serialize,
+)]
 #[repr(i32)]
 pub enum Command {
     #[num_enum(default)]
@@ -26,6 +42,18 @@ pub enum Command {
     PhotonTorpedos = 5,
     #[strum(serialize = "GALACTIC RECORDS")]
     GalacticRecords = 6,
+    #[strum(serialize = "SAVE GAME")]
+    Save = 7,
+    #[strum(serialize = "SHIELD CONTROL")]
+    Shields = 8,
+    #[strum(serialize = "DAMAGE REPORT")]
+    DamageReport = 9,
+    #[strum(serialize = "LIBRARY COMPUTER")]
+    Computer = 10,
+    #[strum(serialize = "DEEP SPACE PROBE")]
+    Probe = 11,
+    #[strum(serialize = "PLANET OPERATIONS")]
+    Planets = 12,
     #[strum(serialize = "QUIT")]
     Quit = -99,
 }
@@ -41,7 +69,7 @@ impl FromStr for Command {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match i32::from_str(s) {
-            Ok(i) => Command::try_from(i),
+            Ok(i) => Ok(Command::from(i)),
             Err(e) => {
                 debug!("Error {} converting \"{}\" => i32 => Command", e, s);
                 Ok(Command::Undefined)
@@ -49,3 +77,47 @@ impl FromStr for Command {
         }
     }
 }
+
+/// An order from the captain, to be carried out by `TheGame::apply`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Move the Enterprise. A course below 1 cancels the move.
+    Warp {
+        course: f64,
+        warp: f64,
+    },
+    ShortRangeScan,
+    LongRangeScan,
+    /// Fire this many units of energy from the phasers, shared out among the Klingons as the
+    /// targeting says
+    Phasers {
+        energy: f64,
+        targeting: PhaserTargeting,
+    },
+    /// Fire a photon torpedo. A course below 1 cancels the shot.
+    Torpedo {
+        course: f64,
+    },
+    /// Fire a torpedo on each of these courses (up to three), with the Klingons answering only
+    /// once all of them have gone
+    TorpedoSpread {
+        courses: Vec<f64>,
+    },
+    GalacticRecords,
+    /// Raise or lower the shields, moving this much energy into them from the main reserves (a
+    /// negative transfer moves it back)
+    Shields {
+        up: bool,
+        transfer: f64,
+    },
+    DamageReport,
+    /// Ask the library computer for a course, or how the mission stands
+    Computer(ComputerRequest),
+    /// Launch a deep-space probe. A course below 1 cancels the launch.
+    Probe {
+        course: f64,
+    },
+    /// Orbit a planet, send the landing party down to mine it, or use what they bring back
+    Planet(PlanetOrder),
+    Quit,
+}
@@ -0,0 +1,233 @@
+//! # startrust::the_game::computer
+//!
+//! The library computer, which works out courses so that the captain doesn't have to. It runs on
+//! the same hardware as the galactic records, and knows only what they know.
+
+use std::cmp::Ordering;
+use std::f64::consts::FRAC_PI_4;
+
+use crate::the_game::damage::Component;
+use crate::the_game::phasers::fnd;
+use crate::the_game::{GameEvent, Quadrant, Sector, SectorContents};
+use crate::{StResult, TheGame};
+
+const COMPUTER: Component = Component::GalacticRecords; // Component #5
+
+/// What the library computer can be asked for
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ComputerRequest {
+    /// The course and distance to each Klingon in the quadrant
+    TorpedoData,
+    /// The course and distance between two sectors (or two quadrants), as (row, column) counted
+    /// from 1
+    Calculator { from: (i32, i32), to: (i32, i32) },
+    /// The course and warp to the nearest starbase in the galactic records
+    NearestStarbase,
+    /// How many Klingons and starbases are left, and how long there is to finish the job
+    StatusReport,
+}
+
+/// A course to a Klingon, for aiming a torpedo
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TorpedoSolution {
+    pub klingon: usize,
+    pub sector: Sector,
+    pub course: f64,
+    pub distance: f64,
+}
+
+/// The course (1 to 8.99) that leads from one (row, column) to another, or `None` if they are the
+/// same place
+pub fn course_between(from: (f64, f64), to: (f64, f64)) -> Option<f64> {
+    let rows = to.0 - from.0;
+    let columns = to.1 - from.1;
+    if rows == 0.0 && columns == 0.0 {
+        return None;
+    }
+    // Course 1 is to the right, and the courses go round counterclockwise, with rows counting down
+    let course = 1.0 + (-rows).atan2(columns) / FRAC_PI_4;
+    Some(if course < 1.0 { course + 8.0 } else { course })
+}
+
+/// Explain why the computer can't be used, if it can't
+pub fn computer_rejection(the_game: &TheGame) -> Vec<GameEvent> {
+    let mut events = Vec::new();
+    if the_game.damage.is_damaged(COMPUTER.into(), false) {
+        // Computer inoperative
+        the_game.damage.show_damage(&mut events, COMPUTER);
+    }
+    events
+}
+
+/// Ask the library computer
+pub fn computer(the_game: &mut TheGame, request: ComputerRequest) -> StResult<()> {
+    match request {
+        ComputerRequest::TorpedoData => {
+            let enterprise = the_game.sector;
+            let targets = the_game
+                .klingons
+                .iter()
+                .filter_map(|klingon| {
+                    let sector = klingon.sector();
+                    course_between(
+                        (enterprise.row() as f64, enterprise.column() as f64),
+                        (sector.row() as f64, sector.column() as f64),
+                    )
+                    .map(|course| TorpedoSolution {
+                        klingon: klingon.id(),
+                        sector,
+                        course,
+                        distance: fnd(sector, enterprise),
+                    })
+                })
+                .collect();
+            the_game.emit(GameEvent::TorpedoData { targets });
+        }
+        ComputerRequest::Calculator { from, to } => {
+            let from = (from.0 as f64, from.1 as f64);
+            let to = (to.0 as f64, to.1 as f64);
+            match course_between(from, to) {
+                Some(course) => the_game.emit(GameEvent::CourseCalculated {
+                    course,
+                    distance: (to.0 - from.0).hypot(to.1 - from.1),
+                }),
+                None => the_game.emit(GameEvent::InvalidOrder),
+            }
+        }
+        ComputerRequest::NearestStarbase => nearest_starbase(the_game),
+        ComputerRequest::StatusReport => the_game.emit(GameEvent::StatusReport {
+            klingons_left: the_game.total_klingons,
+            starbases: the_game.total_starbases,
+            years_left: the_game.ending_stardate - the_game.current_stardate,
+        }),
+    }
+    Ok(())
+} /* End computer */
+
+/// Find the closest starbase that the galactic records know of, and how to get there
+fn nearest_starbase(the_game: &mut TheGame) {
+    let sector_height = the_game.sector_map.height() as f64;
+    let sector_width = the_game.sector_map.width() as f64;
+    // Everything in sectors across the whole galaxy
+    let galactic = |quadrant: Quadrant, row: f64, column: f64| {
+        (
+            quadrant.row() as f64 * sector_height + row,
+            quadrant.column() as f64 * sector_width + column,
+        )
+    };
+    let here = galactic(
+        the_game.quadrant,
+        the_game.sector.row() as f64,
+        the_game.sector.column() as f64,
+    );
+    let nearest = the_game
+        .records
+        .quadrants()
+        .filter(|&quadrant| {
+            // The Enterprise can see its own quadrant, but the rest is only as fresh as the last
+            // look
+            let record = the_game.records[quadrant];
+            quadrant == the_game.quadrant || (!record.is_hidden() && record.starbases > 0)
+        })
+        .filter_map(|quadrant| {
+            let there = if quadrant == the_game.quadrant {
+                let sector = the_game.sector_map.sectors().find(|&sector| {
+                    the_game.sector_map.sector_contents_at(sector) == SectorContents::Starbase
+                })?;
+                galactic(quadrant, sector.row() as f64, sector.column() as f64)
+            } else {
+                // The records don't say where in the quadrant it is, so aim for the middle
+                galactic(
+                    quadrant,
+                    (sector_height - 1.0) / 2.0,
+                    (sector_width - 1.0) / 2.0,
+                )
+            };
+            let distance = (there.0 - here.0).hypot(there.1 - here.1);
+            Some((quadrant, there, distance))
+        })
+        .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal));
+    match nearest {
+        Some((quadrant, there, distance)) => the_game.emit(GameEvent::StarbaseCourse {
+            quadrant,
+            course: course_between(here, there).unwrap_or(1.0),
+            // Warp 1 crosses a quadrant
+            warp: distance / sector_height.max(sector_width),
+        }),
+        None => the_game.emit(GameEvent::NoKnownStarbase),
+    }
+} /* End nearest_starbase */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{new_game, Action};
+
+    #[test]
+    fn test_computer() -> StResult<()> {
+        let mut the_game = new_game(7);
+        the_game.start()?;
+        let events = the_game.apply(Action::Computer(ComputerRequest::TorpedoData))?;
+        let targets = match &events[..] {
+            [GameEvent::TorpedoData { targets }] => targets,
+            _ => panic!("expected torpedo data, got {:?}", events),
+        };
+        assert_eq!(the_game.klingons.len(), targets.len());
+
+        // Each course leads a torpedo to its Klingon
+        for target in targets {
+            let mut the_game = new_game(7);
+            the_game.start()?;
+            let events = the_game.apply(Action::Torpedo {
+                course: target.course,
+            })?;
+            assert!(
+                events.iter().any(|event| matches!(
+                    event,
+                    GameEvent::KlingonDestroyed { klingon, .. } if *klingon == target.klingon
+                )),
+                "{:?} gave {:?}",
+                target,
+                events
+            );
+        }
+
+        assert_eq!(
+            vec![GameEvent::CourseCalculated {
+                course: 3.0,
+                distance: 4.0
+            }],
+            the_game.apply(Action::Computer(ComputerRequest::Calculator {
+                from: (5, 1),
+                to: (1, 1)
+            }))?
+        );
+        assert_eq!(
+            vec![GameEvent::StatusReport {
+                klingons_left: the_game.total_klingons,
+                starbases: the_game.total_starbases,
+                years_left: the_game.ending_stardate - the_game.current_stardate,
+            }],
+            the_game.apply(Action::Computer(ComputerRequest::StatusReport))?
+        );
+
+        // Asking doesn't bring the records up to date, even for the quadrant the Enterprise is in
+        the_game.records[the_game.quadrant].klingons += 1;
+        let record = the_game.records[the_game.quadrant];
+        the_game.apply(Action::Computer(ComputerRequest::NearestStarbase))?;
+        assert_eq!(record, the_game.records[the_game.quadrant]);
+
+        // It goes out with the galactic records
+        the_game
+            .damage
+            .add_damage(Component::GalacticRecords.into(), 1);
+        assert_eq!(
+            vec![GameEvent::ComponentDamaged {
+                component: Component::GalacticRecords,
+                repair_time: 1,
+            }],
+            the_game.apply(Action::Computer(ComputerRequest::NearestStarbase))?
+        );
+        Ok(())
+    }
+}
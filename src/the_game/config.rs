@@ -1,7 +1,84 @@
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{AsRefStr, EnumString};
+
 use crate::the_game::stardate::StarDate;
+use crate::util::RandomMode;
+use crate::{StResult, StarTrustError};
+
+/// The galaxy can be from 2x2 to 16x16 quadrants
+const GALAXY_SIZES: RangeInclusive<i32> = 2..=16;
+/// A quadrant needs at least 4x4 sectors to hold the Enterprise, the most Klingons a quadrant can
+/// have and a starbase; whatever else doesn't fit in a small quadrant is left out
+const QUADRANT_SIZES: RangeInclusive<i32> = 4..=16;
+
+/// How hard the game is
+#[derive(
+    AsRefStr, Copy, Clone, Debug, Default, Deserialize, EnumString, Eq, PartialEq, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    #[strum(serialize = "cadet")]
+    Cadet,
+    #[strum(serialize = "lieutenant")]
+    Lieutenant,
+    /// The original game
+    #[default]
+    #[strum(serialize = "captain")]
+    Captain,
+    #[strum(serialize = "admiral")]
+    Admiral,
+}
 
-#[derive(Builder, Copy, Clone, Debug)]
-#[builder(default)]
+impl Difficulty {
+    /// How likely a quadrant is to have Klingons (`x1`), their shields (`s9`), how rare starbases
+    /// are, the years available and how often space storms strike
+    fn preset(self) -> (f64, f64, f64, i32, f64) {
+        match self {
+            Difficulty::Cadet => (0.12, 250.0, 0.92, 40, 0.15),
+            Difficulty::Lieutenant => (0.16, 320.0, 0.94, 35, 0.2),
+            Difficulty::Captain => (0.2075, 400.0, 0.96, 30, 0.25),
+            Difficulty::Admiral => (0.26, 500.0, 0.98, 25, 0.35),
+        }
+    }
+
+    /// Set up game definitions for this difficulty
+    pub fn apply(self, builder: &mut TheGameDefsBuilder) {
+        let (x1, s9, starbase_frequency, years, storm_probability) = self.preset();
+        let beginning_stardate = builder
+            .beginning_stardate
+            .unwrap_or_else(|| TheGameDefs::default().beginning_stardate);
+        builder
+            .difficulty(self)
+            .x1(x1)
+            .s9(s9)
+            .starbase_frequency(starbase_frequency)
+            .ending_stardate(beginning_stardate + years)
+            .storm_probability(storm_probability);
+    }
+
+    /// The years a game lasts at this difficulty
+    pub fn years(self) -> i32 {
+        self.preset().3
+    }
+
+    /// How much a win counts for at this difficulty
+    pub fn rating_factor(self) -> f64 {
+        match self {
+            Difficulty::Cadet => 0.5,
+            Difficulty::Lieutenant => 0.75,
+            Difficulty::Captain => 1.0,
+            Difficulty::Admiral => 1.5,
+        }
+    }
+}
+
+#[derive(Builder, Copy, Clone, Debug, Deserialize, Serialize)]
+#[builder(default, build_fn(validate = "Self::validate"))]
+#[serde(default)] // Saves from before a setting existed get its default
 pub struct TheGameDefs {
     /// Initial Energy
     pub(crate) initial_energy: f64, // Probably could be `f32`
@@ -13,6 +90,8 @@ pub struct TheGameDefs {
     pub(crate) ending_stardate: StarDate,
     /// Initial total Klingons
     pub(crate) initial_total_klingons: i32,
+    /// The chance that a quadrant has no starbase (a quadrant gets one when a random number comes
+    /// out above this)
     pub(crate) starbase_frequency: f64,
     pub(crate) s9: f64,
     x: i32,
@@ -21,6 +100,39 @@ pub struct TheGameDefs {
     pub(crate) y1: f64,
     pub(crate) x2: f64,
     pub(crate) y2: f64,
+    /// Seed for the game's random numbers (a fresh one is picked for each game if not set)
+    #[builder(setter(strip_option))]
+    pub(crate) seed: Option<u64>,
+    /// Which random number generator to use
+    pub(crate) random_mode: RandomMode,
+    /// The number of quadrants across the galaxy
+    pub(crate) galaxy_width: i32,
+    /// The number of quadrants down the galaxy
+    pub(crate) galaxy_height: i32,
+    /// The number of sectors across a quadrant
+    pub(crate) quadrant_width: i32,
+    /// The number of sectors down a quadrant
+    pub(crate) quadrant_height: i32,
+    /// The difficulty these definitions were set up for
+    pub(crate) difficulty: Difficulty,
+    /// The chance of a space storm (or a repair) on each move
+    pub(crate) storm_probability: f64,
+    /// The chance that a group of Klingons moves to another quadrant each stardate
+    pub(crate) migration_probability: f64,
+    /// Initial deep-space probes
+    pub(crate) initial_probes: i32,
+    /// How likely each quadrant is to have Romulans, from 0 to 1
+    pub(crate) romulan_frequency: f64,
+    /// The shields each Romulan starts with
+    pub(crate) romulan_shields: f64,
+    /// How likely each quadrant is to have a planet, from 0 to 1
+    pub(crate) planet_frequency: f64,
+    /// How likely each quadrant is to have a black hole, from 0 to 1
+    pub(crate) black_hole_frequency: f64,
+    /// How likely each quadrant is to have a nebula, from 0 to 1
+    pub(crate) nebula_frequency: f64,
+    /// The average number of years between supernovas elsewhere in the galaxy (0 for none)
+    pub(crate) supernova_interval: i32,
 }
 
 impl TheGameDefs {
@@ -29,7 +141,259 @@ impl TheGameDefs {
     }
 }
 
+impl TheGameDefsBuilder {
+    /// Refuse settings that would make an unplayable game
+    fn validate(&self) -> Result<(), String> {
+        let defaults = TheGameDefs::default();
+        let initial_energy = self.initial_energy.unwrap_or(defaults.initial_energy);
+        if initial_energy.is_nan() || initial_energy <= 0.0 {
+            return Err(format!("energy must be positive, not {}", initial_energy));
+        }
+        let initial_photon_torpedoes = self
+            .initial_photon_torpedoes
+            .unwrap_or(defaults.initial_photon_torpedoes);
+        if initial_photon_torpedoes < 0 {
+            return Err(format!(
+                "torpedoes can't be negative, but was {}",
+                initial_photon_torpedoes
+            ));
+        }
+        let initial_probes = self.initial_probes.unwrap_or(defaults.initial_probes);
+        if initial_probes < 0 {
+            return Err(format!(
+                "probes can't be negative, but was {}",
+                initial_probes
+            ));
+        }
+        let beginning_stardate = self
+            .beginning_stardate
+            .unwrap_or(defaults.beginning_stardate);
+        let ending_stardate = self.ending_stardate.unwrap_or(defaults.ending_stardate);
+        if ending_stardate <= beginning_stardate {
+            return Err(format!(
+                "the game must last at least a year, but ends at stardate {} and begins at {}",
+                ending_stardate, beginning_stardate
+            ));
+        }
+        let starbase_frequency = self
+            .starbase_frequency
+            .unwrap_or(defaults.starbase_frequency);
+        if !(0.0..=1.0).contains(&starbase_frequency) {
+            return Err(format!(
+                "starbase_frequency must be between 0 and 1, not {}",
+                starbase_frequency
+            ));
+        }
+        let storm_probability = self.storm_probability.unwrap_or(defaults.storm_probability);
+        if !(0.0..=1.0).contains(&storm_probability) {
+            return Err(format!(
+                "storm_probability must be between 0 and 1, not {}",
+                storm_probability
+            ));
+        }
+        let migration_probability = self
+            .migration_probability
+            .unwrap_or(defaults.migration_probability);
+        if !(0.0..=1.0).contains(&migration_probability) {
+            return Err(format!(
+                "migration_probability must be between 0 and 1, not {}",
+                migration_probability
+            ));
+        }
+        let romulan_frequency = self.romulan_frequency.unwrap_or(defaults.romulan_frequency);
+        if !(0.0..=1.0).contains(&romulan_frequency) {
+            return Err(format!(
+                "romulan_frequency must be between 0 and 1, not {}",
+                romulan_frequency
+            ));
+        }
+        let romulan_shields = self.romulan_shields.unwrap_or(defaults.romulan_shields);
+        if romulan_shields.is_nan() || romulan_shields <= 0.0 {
+            return Err(format!(
+                "romulan_shields must be positive, not {}",
+                romulan_shields
+            ));
+        }
+        let planet_frequency = self.planet_frequency.unwrap_or(defaults.planet_frequency);
+        if !(0.0..=1.0).contains(&planet_frequency) {
+            return Err(format!(
+                "planet_frequency must be between 0 and 1, not {}",
+                planet_frequency
+            ));
+        }
+        for (name, frequency) in [
+            (
+                "black_hole_frequency",
+                self.black_hole_frequency
+                    .unwrap_or(defaults.black_hole_frequency),
+            ),
+            (
+                "nebula_frequency",
+                self.nebula_frequency.unwrap_or(defaults.nebula_frequency),
+            ),
+        ] {
+            if !(0.0..=1.0).contains(&frequency) {
+                return Err(format!(
+                    "{} must be between 0 and 1, not {}",
+                    name, frequency
+                ));
+            }
+        }
+        let supernova_interval = self
+            .supernova_interval
+            .unwrap_or(defaults.supernova_interval);
+        if supernova_interval < 0 {
+            return Err(format!(
+                "supernova_interval can't be negative, not {}",
+                supernova_interval
+            ));
+        }
+        for (name, size, range) in [
+            (
+                "galaxy_width",
+                self.galaxy_width.unwrap_or(defaults.galaxy_width),
+                GALAXY_SIZES,
+            ),
+            (
+                "galaxy_height",
+                self.galaxy_height.unwrap_or(defaults.galaxy_height),
+                GALAXY_SIZES,
+            ),
+            (
+                "quadrant_width",
+                self.quadrant_width.unwrap_or(defaults.quadrant_width),
+                QUADRANT_SIZES,
+            ),
+            (
+                "quadrant_height",
+                self.quadrant_height.unwrap_or(defaults.quadrant_height),
+                QUADRANT_SIZES,
+            ),
+        ]
+        .iter()
+        {
+            if !range.contains(size) {
+                return Err(format!(
+                    "{} must be from {} to {}, not {}",
+                    name,
+                    range.start(),
+                    range.end(),
+                    size
+                ));
+            }
+        }
+        let s9 = self.s9.unwrap_or(defaults.s9);
+        if s9.is_nan() || s9 <= 0.0 {
+            return Err(format!("klingon_shields must be positive, not {}", s9));
+        }
+        Ok(())
+    }
+}
+
+/// Game settings from a configuration file or the command line. Anything left out keeps its
+/// default value.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GameConfig {
+    /// The preset the other settings are applied on top of
+    pub difficulty: Option<Difficulty>,
+    /// Initial energy
+    pub energy: Option<f64>,
+    /// Initial photon torpedoes
+    pub torpedoes: Option<i32>,
+    /// The stardate the game begins on
+    pub stardate: Option<i32>,
+    /// How many years there are to destroy the Klingons
+    pub years: Option<i32>,
+    /// How likely each quadrant is to have *no* starbase, from 0 to 1, so that a higher value
+    /// means fewer starbases (the name is kept from the original game's settings)
+    pub starbase_frequency: Option<f64>,
+    /// The shield energy of each Klingon (`s9`)
+    pub klingon_shields: Option<f64>,
+    pub galaxy_width: Option<i32>,
+    pub galaxy_height: Option<i32>,
+    pub quadrant_width: Option<i32>,
+    pub quadrant_height: Option<i32>,
+    pub seed: Option<u64>,
+}
+
+impl GameConfig {
+    /// Read a TOML configuration file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> StResult<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text)
+            .map_err(|e| StarTrustError::ConfigError(format!("in {}: {}", path.display(), e)))
+    }
+
+    /// Replace these settings with any that are given in `overrides`
+    pub fn override_with(&mut self, overrides: &GameConfig) {
+        self.difficulty = overrides.difficulty.or(self.difficulty);
+        self.energy = overrides.energy.or(self.energy);
+        self.torpedoes = overrides.torpedoes.or(self.torpedoes);
+        self.stardate = overrides.stardate.or(self.stardate);
+        self.years = overrides.years.or(self.years);
+        self.starbase_frequency = overrides.starbase_frequency.or(self.starbase_frequency);
+        self.klingon_shields = overrides.klingon_shields.or(self.klingon_shields);
+        self.galaxy_width = overrides.galaxy_width.or(self.galaxy_width);
+        self.galaxy_height = overrides.galaxy_height.or(self.galaxy_height);
+        self.quadrant_width = overrides.quadrant_width.or(self.quadrant_width);
+        self.quadrant_height = overrides.quadrant_height.or(self.quadrant_height);
+        self.seed = overrides.seed.or(self.seed);
+    }
+
+    /// Put these settings into game definitions that are being built
+    pub fn apply(&self, builder: &mut TheGameDefsBuilder) {
+        let defaults = TheGameDefs::default();
+        let difficulty = self.difficulty.unwrap_or_default();
+        difficulty.apply(builder);
+        if let Some(energy) = self.energy {
+            builder.initial_energy(energy);
+        }
+        if let Some(torpedoes) = self.torpedoes {
+            builder.initial_photon_torpedoes(torpedoes);
+        }
+        if self.stardate.is_some() || self.years.is_some() {
+            let beginning_stardate = self
+                .stardate
+                .map_or(defaults.beginning_stardate, StarDate::new);
+            let years = self.years.unwrap_or_else(|| difficulty.years());
+            builder.beginning_stardate(beginning_stardate);
+            builder.ending_stardate(beginning_stardate + years);
+        }
+        if let Some(starbase_frequency) = self.starbase_frequency {
+            builder.starbase_frequency(starbase_frequency);
+        }
+        if let Some(klingon_shields) = self.klingon_shields {
+            builder.s9(klingon_shields);
+        }
+        if let Some(galaxy_width) = self.galaxy_width {
+            builder.galaxy_width(galaxy_width);
+        }
+        if let Some(galaxy_height) = self.galaxy_height {
+            builder.galaxy_height(galaxy_height);
+        }
+        if let Some(quadrant_width) = self.quadrant_width {
+            builder.quadrant_width(quadrant_width);
+        }
+        if let Some(quadrant_height) = self.quadrant_height {
+            builder.quadrant_height(quadrant_height);
+        }
+        if let Some(seed) = self.seed {
+            builder.seed(seed);
+        }
+    }
+
+    /// Build game definitions from these settings, checking that they make sense
+    pub fn build(&self) -> StResult<TheGameDefs> {
+        let mut builder = TheGameDefsBuilder::default();
+        self.apply(&mut builder);
+        builder.build().map_err(StarTrustError::ConfigError)
+    }
+}
+
 impl Default for TheGameDefs {
+    #[allow(clippy::approx_constant)] // `y1` is a game constant, not an approximation of TAU
     fn default() -> Self {
         let e0 = 4000.0;
         let p0 = 10;
@@ -59,6 +423,22 @@ impl Default for TheGameDefs {
             starbase_frequency: aa,
             s9,
             initial_total_klingons: k9,
+            seed: None,
+            random_mode: RandomMode::Standard,
+            galaxy_width: 8,
+            galaxy_height: 8,
+            quadrant_width: 8,
+            quadrant_height: 8,
+            difficulty: Difficulty::Captain,
+            storm_probability: 0.25,
+            migration_probability: 0.2,
+            initial_probes: 3,
+            romulan_frequency: 0.1,
+            romulan_shields: 600.0,
+            planet_frequency: 0.3,
+            black_hole_frequency: 0.05,
+            nebula_frequency: 0.1,
+            supernova_interval: 10,
         }
     }
 }
@@ -75,4 +455,58 @@ mod tests {
         assert_eq!(StarDate::new(3451), the_game_defs.ending_stardate);
         Ok(())
     }
+
+    #[test]
+    fn test_config_overrides() -> StResult<()> {
+        let mut config: GameConfig =
+            toml::from_str("energy = 3000.0\nyears = 20\nklingon_shields = 300.0\n").unwrap();
+        config.override_with(&GameConfig {
+            energy: Some(5000.0),
+            torpedoes: Some(4),
+            ..GameConfig::default()
+        });
+        let the_game_defs = config.build()?;
+        assert_eq!(5000.0, the_game_defs.initial_energy);
+        assert_eq!(4, the_game_defs.initial_photon_torpedoes);
+        assert_eq!(StarDate::new(3441), the_game_defs.ending_stardate);
+        assert_eq!(300.0, the_game_defs.s9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_difficulty_presets() -> StResult<()> {
+        let captain = GameConfig {
+            difficulty: Some(Difficulty::Captain),
+            ..GameConfig::default()
+        }
+        .build()?;
+        let defaults = TheGameDefs::default();
+        assert_eq!(defaults.x1, captain.x1);
+        assert_eq!(defaults.ending_stardate, captain.ending_stardate);
+
+        let config: GameConfig = toml::from_str("difficulty = \"admiral\"\nyears = 50\n").unwrap();
+        let admiral = config.build()?;
+        assert_eq!(Difficulty::Admiral, admiral.difficulty);
+        assert!(admiral.s9 > defaults.s9);
+        assert_eq!(StarDate::new(3471), admiral.ending_stardate);
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_errors() {
+        assert!(toml::from_str::<GameConfig>("energy = 3000.0\nphasers = 2\n").is_err());
+        let config = GameConfig {
+            starbase_frequency: Some(1.5),
+            ..GameConfig::default()
+        };
+        assert!(matches!(
+            config.build(),
+            Err(StarTrustError::ConfigError(_))
+        ));
+        let config = GameConfig {
+            years: Some(0),
+            ..GameConfig::default()
+        };
+        assert!(config.build().is_err());
+    }
 }
@@ -1,17 +1,22 @@
-//!
+//! # startrust::the_game::damage
 
 use std::fmt::{Display, Formatter};
 
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
 use strum_macros::{AsRefStr, EnumString};
-use termcolor::WriteColor;
 
-use crate::interaction::beep;
-use crate::StResult;
+use crate::the_game::GameEvent;
+use crate::{StResult, TheGame};
+
+const DAMAGE_CONTROL: Component = Component::DamageControl; // Component #6
+
+/// The number of components that can be damaged
+pub(crate) const COMPONENTS: usize = 7;
 
 #[derive(AsRefStr, Debug, PartialEq, EnumString, IntoPrimitive, TryFromPrimitive, Copy, Clone)]
 #[repr(usize)]
-pub(crate) enum Component {
+pub enum Component {
     #[strum(serialize = "WARP ENGINES")]
     WarpEngines = 0,
     #[strum(serialize = "SHORT RANGE SENSORS")]
@@ -24,6 +29,25 @@ pub(crate) enum Component {
     PhotonTorpedoes = 4,
     #[strum(serialize = "GALACTIC RECORDS")]
     GalacticRecords = 5,
+    #[strum(serialize = "DAMAGE CONTROL SYSTEMS")]
+    DamageControl = 6,
+}
+
+impl Component {
+    /// Every component, in order
+    pub fn all() -> impl Iterator<Item = Component> {
+        (0..COMPONENTS).filter_map(|i| Component::try_from_primitive(i).ok())
+    }
+}
+
+/// How one component stands, for the damage report
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ComponentStatus {
+    pub component: Component,
+    /// The estimated years until it is repaired, 0 if it works
+    pub repair_time: i32,
+    /// The years of repairs already done on it
+    pub repairs_done: i32,
 }
 
 impl Display for Component {
@@ -32,66 +56,141 @@ impl Display for Component {
     }
 }
 
-pub(crate) struct Damage(Vec<i32>);
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Damage {
+    /// The estimated years until each component is repaired
+    repair_times: Vec<i32>,
+    /// The longest each component's current repairs have been estimated to take, to measure
+    /// their progress against
+    worst_repair_times: Vec<i32>,
+}
 
 impl Damage {
     pub(crate) fn new() -> Self {
-        Self(vec![0i32; 6])
+        Self {
+            repair_times: vec![0i32; COMPONENTS],
+            worst_repair_times: vec![0i32; COMPONENTS],
+        }
     }
 
     /// Repair anything that is down
     pub fn fix_damage(&mut self) {
-        for i in 0..6 {
-            self.0[i] = 0;
+        for i in 0..COMPONENTS {
+            self.repair_times[i] = 0;
+            self.worst_repair_times[i] = 0;
         }
     } /* End fixdamage */
 
-    #[allow(dead_code)]
-    fn get_damage(&self, i: Component) -> i32 {
+    /// The estimated years until a component is repaired
+    pub(crate) fn get_damage(&self, i: Component) -> i32 {
         let i: usize = i.into();
-        self.0[i]
+        self.repair_times[i]
+    }
+
+    /// The years of repairs already done on a component
+    pub(crate) fn get_repairs_done(&self, i: Component) -> i32 {
+        let i: usize = i.into();
+        (self.worst_repair_times[i] - self.repair_times[i]).max(0)
     }
 
     pub(crate) fn set_damage(&mut self, i: usize, value: i32) {
-        self.0[i] = value
+        self.repair_times[i] = value;
+        self.worst_repair_times[i] = self.worst_repair_times[i].max(value);
     }
 
     pub(crate) fn add_damage(&mut self, x: usize, increment: i32) {
-        self.0[x] += increment;
+        self.set_damage(x, self.repair_times[x] + increment);
     }
 
     pub(crate) fn is_damaged(&self, i: usize, no_negativity: bool) -> bool {
         if no_negativity {
-            self.0[i] != 0
+            self.repair_times[i] != 0
         } else {
-            self.0[i] > 0
+            self.repair_times[i] > 0
         }
     }
 
     pub(crate) fn reduce_and_normalize_damage(&mut self, i: usize) -> bool {
-        self.0[i] -= 1;
-        if self.0[i] <= 0 {
-            self.0[i] = 0;
+        self.repair_times[i] -= 1;
+        if self.repair_times[i] <= 0 {
+            self.repair_times[i] = 0;
+            self.worst_repair_times[i] = 0;
             true
         } else {
             false
         }
     }
 
-    /// Show estimated time for repair
-    pub(crate) fn show_est_repair_time<W: WriteColor>(
-        &self,
-        sout: &mut W,
-        i: usize,
-    ) -> StResult<()> {
-        writeln!(sout, "{} YEARS ESTIMATED FOR REPAIR.\n", self.0[i]).map_err(|e| e.into())
-    } /* End showestreptime */
-
-    /// Show damaged item
-    pub(crate) fn show_damage<W: WriteColor>(&self, sout: &mut W, i: Component) -> StResult<()> {
-        write!(sout, "{} DAMAGED.  ", i.as_ref())?;
-        sout.flush()?;
-        beep();
-        self.show_est_repair_time(sout, i.into())
+    /// Report a damaged item
+    pub(crate) fn show_damage(&self, events: &mut Vec<GameEvent>, i: Component) {
+        events.push(GameEvent::ComponentDamaged {
+            component: i,
+            repair_time: self.get_damage(i),
+        });
     } /* End showdamage */
 }
+
+/// Explain why there can't be a damage report, if there can't
+pub fn damage_report_rejection(the_game: &TheGame) -> Vec<GameEvent> {
+    let mut events = Vec::new();
+    if the_game.damage.is_damaged(DAMAGE_CONTROL.into(), false) {
+        the_game.damage.show_damage(&mut events, DAMAGE_CONTROL);
+    }
+    events
+}
+
+/// Report on every component, damaged or not
+pub fn damage_report(the_game: &mut TheGame) -> StResult<()> {
+    let components = Component::all()
+        .map(|component| ComponentStatus {
+            component,
+            repair_time: the_game.damage.get_damage(component),
+            repairs_done: the_game.damage.get_repairs_done(component),
+        })
+        .collect();
+    the_game.emit(GameEvent::DamageReport { components });
+    Ok(())
+} /* End damagereport */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{new_game, Action};
+
+    #[test]
+    fn test_damage_report() -> StResult<()> {
+        let mut the_game = new_game(2);
+        the_game.start()?;
+        the_game.damage.add_damage(Component::Phasers.into(), 4);
+        the_game
+            .damage
+            .reduce_and_normalize_damage(Component::Phasers.into());
+        let events = the_game.apply(Action::DamageReport)?;
+        let components = match &events[..] {
+            [GameEvent::DamageReport { components }] => components,
+            _ => panic!("expected a damage report, got {:?}", events),
+        };
+        assert_eq!(COMPONENTS, components.len());
+        assert_eq!(
+            ComponentStatus {
+                component: Component::Phasers,
+                repair_time: 3,
+                repairs_done: 1,
+            },
+            components[usize::from(Component::Phasers)]
+        );
+
+        // It works with phasers down, but not with damage control down
+        the_game
+            .damage
+            .add_damage(Component::DamageControl.into(), 2);
+        assert_eq!(
+            vec![GameEvent::ComponentDamaged {
+                component: Component::DamageControl,
+                repair_time: 2,
+            }],
+            the_game.apply(Action::DamageReport)?
+        );
+        Ok(())
+    }
+}
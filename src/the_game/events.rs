@@ -0,0 +1,392 @@
+//! # startrust::the_game::events
+//!
+//! What the game tells its frontend. The game itself never writes anything; every action returns
+//! the events it caused, and it is up to the frontend to show them to the player.
+
+use crate::the_game::computer::TorpedoSolution;
+use crate::the_game::damage::{Component, ComponentStatus};
+use crate::the_game::planets::{LandingCraft, PlanetClass};
+use crate::the_game::quadrant::{Quadrant, QuadrantContents};
+use crate::the_game::sector::{Sector, SectorContents};
+use crate::the_game::stardate::StarDate;
+use crate::the_game::Condition;
+
+/// Something that happened in the game
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// The galaxy has been set up, and this is the mission
+    GameStarted {
+        klingons: i32,
+        years: i32,
+        starbases: i32,
+    },
+    /// The Enterprise has come out of hyperspace in a quadrant
+    QuadrantEntered {
+        quadrant: Quadrant,
+    },
+    /// The Enterprise has moved to another sector of the same quadrant
+    MovedWithinQuadrant {
+        sector: Sector,
+    },
+    /// The Enterprise was stopped short by something in its way
+    Blocked {
+        by: SectorContents,
+        sector: Sector,
+    },
+    /// The Enterprise is next to a starbase, and has been resupplied and repaired
+    Docked,
+    /// The Klingons can't fire on the Enterprise while it is docked
+    StarbaseProtects,
+    /// A Klingon fired on the Enterprise
+    EnterpriseHit {
+        from: Sector,
+        /// The id of the Klingon that fired
+        klingon: usize,
+        hit: f64,
+        energy_left: f64,
+        /// What is left in the shields, if they are up
+        shields_left: Option<f64>,
+    },
+    /// The phasers hit a Klingon
+    KlingonHit {
+        sector: Sector,
+        klingon: usize,
+        hit: f64,
+        shields_left: f64,
+    },
+    KlingonDestroyed {
+        sector: Sector,
+        klingon: usize,
+        weapon: Weapon,
+    },
+    /// A Klingon manoeuvred to another sector
+    KlingonMoved {
+        klingon: usize,
+        from: Sector,
+        to: Sector,
+    },
+    /// A worn-down Klingon warped out of the quadrant
+    KlingonFled {
+        klingon: usize,
+        sector: Sector,
+        to: Quadrant,
+    },
+    /// A photon torpedo has been launched
+    TorpedoFired,
+    /// A photon torpedo passed through a sector
+    TorpedoTrack {
+        sector: Sector,
+    },
+    /// A photon torpedo left the quadrant without hitting anything
+    TorpedoMissed,
+    /// A star hit by a torpedo has gone nova
+    Nova {
+        sector: Sector,
+    },
+    /// A nova pushed a Klingon away
+    KlingonDisplaced {
+        klingon: usize,
+        from: Sector,
+        to: Sector,
+    },
+    /// A nova next to the Enterprise hit it
+    EnterpriseBuffeted {
+        hit: f64,
+        energy_left: f64,
+        /// What is left in the shields, if they are up
+        shields_left: Option<f64>,
+    },
+    /// A nova pushed the Enterprise to another sector
+    EnterpriseDisplaced {
+        sector: Sector,
+    },
+    /// A star went supernova, destroying everything in the quadrant (the Enterprise's own, or one
+    /// that Starfleet reports on)
+    Supernova {
+        quadrant: Quadrant,
+        klingons: i32,
+        starbases: i32,
+    },
+    /// The supernova's shock wave threw the Enterprise into a neighbouring quadrant
+    ThrownClear {
+        quadrant: Quadrant,
+        hit: f64,
+        energy_left: f64,
+    },
+    /// The navigation computer stopped the Enterprise short of a supernova's remains
+    WarpDiverted {
+        quadrant: Quadrant,
+    },
+    /// With the computer down, the Enterprise flew into a supernova's remains
+    EnteredSupernova {
+        quadrant: Quadrant,
+    },
+    StarbaseDestroyed {
+        sector: Sector,
+    },
+    /// A distress call from a starbase the Klingons are attacking
+    StarbaseUnderSiege {
+        quadrant: Quadrant,
+        /// When the starbase will fall, unless the Enterprise gets there first
+        deadline: StarDate,
+    },
+    /// The Enterprise's arrival drove off the Klingons besieging a starbase
+    SiegeRelieved {
+        quadrant: Quadrant,
+    },
+    /// A besieged starbase fell to the Klingons
+    StarbaseLost {
+        quadrant: Quadrant,
+    },
+    /// A space storm damaged a component
+    SpaceStorm {
+        component: Component,
+        repair_time: i32,
+    },
+    /// Spock's new repair technique leaves the component one year from being fixed
+    SpockRepair {
+        component: Component,
+    },
+    RepairCompleted {
+        component: Component,
+    },
+    /// A Klingon hit got past the shields and damaged a component
+    HitDamage {
+        component: Component,
+        repair_time: i32,
+    },
+    /// The component needed for an action is damaged
+    ComponentDamaged {
+        component: Component,
+        repair_time: i32,
+    },
+    /// The damaged warp engines can't go faster than `max_warp`
+    WarpLimited {
+        max_warp: f64,
+        repair_time: i32,
+    },
+    /// The damaged torpedo tubes can't fire
+    TubesBlocked {
+        repair_time: i32,
+    },
+    NoTorpedoesLeft,
+    NoProbesLeft,
+    /// A deep-space probe is on its way
+    ProbeLaunched {
+        probes_left: i32,
+    },
+    /// A probe has passed through a quadrant and seen what is in it
+    ProbeReport {
+        quadrant: Quadrant,
+        contents: QuadrantContents,
+    },
+    /// A probe has left the galaxy, and is gone
+    ProbeLost,
+    /// A Romulan has fired on the Enterprise
+    EnterpriseHitByRomulan {
+        from: Sector,
+        hit: f64,
+        energy_left: f64,
+        /// What is left in the shields, if they are up
+        shields_left: Option<f64>,
+    },
+    /// A torpedo has destroyed a Romulan, who may not have deserved it
+    RomulanDestroyed {
+        sector: Sector,
+        provoked: bool,
+    },
+    /// The Romulans in the quadrant have been attacked or rammed, and are fighting back
+    RomulansProvoked {
+        romulans: i32,
+    },
+    /// The Romulans in the quadrant have cloaked, or dropped their cloak
+    RomulansCloaked {
+        cloaked: bool,
+    },
+    /// A photon torpedo burned up in a planet's atmosphere
+    PlanetHit {
+        sector: Sector,
+    },
+    /// A photon torpedo fell into a black hole
+    TorpedoSwallowed {
+        sector: Sector,
+    },
+    /// A photon torpedo was lost in a nebula
+    TorpedoLostInNebula {
+        sector: Sector,
+    },
+    /// The Enterprise has been pulled into a black hole
+    SwallowedByBlackHole {
+        sector: Sector,
+    },
+    /// A nebula scattered the phaser fire before it reached a Klingon
+    PhaserOutOfRange {
+        sector: Sector,
+        klingon: usize,
+    },
+    /// There is no planet next to the Enterprise to orbit
+    NoPlanetNearby,
+    /// The Enterprise is in standard orbit around a planet
+    EnteredOrbit {
+        class: PlanetClass,
+    },
+    /// The landing party can only go down (or come back) from orbit
+    NotInOrbit,
+    /// The transporter can't work through raised shields
+    TransporterBlockedByShields,
+    /// The landing party is on the planet
+    PartyLanded {
+        by: LandingCraft,
+    },
+    /// The landing party is back aboard, perhaps with crystals
+    PartyReturned {
+        by: LandingCraft,
+        crystals: bool,
+    },
+    /// There is no landing party on the planet to do the job
+    PartyNotAshore,
+    /// The Enterprise can't leave with the landing party still on the planet
+    PartyAshore,
+    /// The landing party was on the planet when its quadrant went supernova
+    PartyLost,
+    /// The landing party has dug up a load of dilithium crystals
+    CrystalsMined,
+    /// The planet has no dilithium (or none left)
+    NoDilithiumFound,
+    /// There are no crystals aboard to use
+    NoCrystals,
+    /// The crystals took, and boosted the energy to this
+    CrystalsUsed {
+        energy: f64,
+    },
+    /// The crystals fractured, and damaged the warp engines
+    CrystalsFailed {
+        repair_time: i32,
+    },
+    /// There aren't enough torpedoes left for the spread
+    NotEnoughTorpedoes {
+        torpedoes: i32,
+    },
+    /// There isn't this much energy to fire
+    NotEnoughEnergy {
+        energy: f64,
+    },
+    /// There isn't this much energy in the shields to move back to the main reserves
+    NotEnoughShieldEnergy {
+        shields: f64,
+    },
+    /// The shields have been raised or lowered, or had energy moved in or out
+    ShieldsSet {
+        up: bool,
+        shields: f64,
+        energy: f64,
+    },
+    /// An order that makes no sense, such as a course of 10
+    InvalidOrder,
+    /// The results of a short range sensor scan
+    ShortRangeScan(ShortRangeScan),
+    /// The quadrants around the Enterprise, centred on the current quadrant
+    LongRangeScan {
+        /// The current quadrant, counted from 1
+        quadrant: (i32, i32),
+        /// The scanned quadrants, `None` for those outside of the galaxy
+        quadrants: Vec<Vec<Option<QuadrantContents>>>,
+    },
+    /// The cumulative galactic map
+    GalacticRecords {
+        stardate: StarDate,
+        quadrants: Vec<Vec<QuadrantContents>>,
+        /// The current quadrant, as indices into `quadrants`
+        current: (usize, usize),
+    },
+    /// How every component stands
+    DamageReport {
+        components: Vec<ComponentStatus>,
+    },
+    /// The computer's courses to the Klingons in the quadrant
+    TorpedoData {
+        targets: Vec<TorpedoSolution>,
+    },
+    /// The computer's course and distance between two points
+    CourseCalculated {
+        course: f64,
+        distance: f64,
+    },
+    /// The course and warp to the nearest starbase in the galactic records
+    StarbaseCourse {
+        quadrant: Quadrant,
+        course: f64,
+        warp: f64,
+    },
+    /// The galactic records don't show any starbases
+    NoKnownStarbase,
+    /// The computer's summary of the mission so far
+    StatusReport {
+        klingons_left: i32,
+        starbases: i32,
+        years_left: i32,
+    },
+    /// The game is over
+    GameOver {
+        stardate: StarDate,
+        ending: Ending,
+        /// Where starbases fell to Klingon sieges
+        starbases_lost: Vec<Quadrant>,
+    },
+}
+
+/// What destroyed a Klingon
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Weapon {
+    Phasers,
+    PhotonTorpedo,
+    /// Caught by a nova the Enterprise set off
+    Nova,
+}
+
+/// How a game ended
+#[derive(Clone, Debug, PartialEq)]
+pub enum Ending {
+    Won {
+        klingons_destroyed: i32,
+        years: i32,
+        /// Less a penalty for each unprovoked Romulan kill
+        rating: i32,
+        unprovoked_romulan_kills: i32,
+        /// Klingons lost to supernovas, which count for less in the rating
+        supernova_kills: i32,
+    },
+    RanOutOfTime {
+        klingons_left: i32,
+    },
+    RanOutOfEnergy {
+        klingons_left: i32,
+    },
+    SwallowedByBlackHole {
+        klingons_left: i32,
+    },
+    /// A supernova with nowhere to escape to
+    CaughtInSupernova {
+        klingons_left: i32,
+    },
+    Quit,
+}
+
+/// The sector map and ship status from the short range sensors
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShortRangeScan {
+    /// The contents of each sector, row by row
+    pub sectors: Vec<Vec<SectorContents>>,
+    pub years_left: i32,
+    pub stardate: StarDate,
+    pub condition: Condition,
+    /// The current quadrant, counted from 1
+    pub quadrant: (i32, i32),
+    /// The Enterprise's sector, counted from 1
+    pub sector: (i32, i32),
+    pub energy: f64,
+    pub photon_torpedoes: i32,
+    pub klingons_left: i32,
+    pub shields: f64,
+    pub shields_up: bool,
+}
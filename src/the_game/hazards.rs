@@ -0,0 +1,138 @@
+//! # startrust::the_game::hazards
+//!
+//! Black holes and nebulae. A black hole swallows any torpedo that reaches it, and a ship that
+//! passes too close may be pulled in, never to be seen again. A nebula stops ships and torpedoes
+//! like a star does, but also hides its quadrant from the long range sensors and scatters phaser
+//! fire, so that the phasers can only reach nearby Klingons.
+
+use crate::the_game::{fnd, GameEvent, GameState, Sector, SectorContents};
+use crate::util::rnd;
+use crate::TheGame;
+
+/// How far the phasers reach in a quadrant with a nebula, in sectors
+const NEBULA_PHASER_RANGE: f64 = 4.0;
+/// The chance that a black hole pulls in a ship passing through a sector next to it
+const BLACK_HOLE_PULL_PROBABILITY: f64 = 0.25;
+/// The most sectors one nebula covers
+const MAX_NEBULA_SECTORS: i32 = 4;
+
+/// Scatter black holes and nebulae across the galaxy as it is set up
+pub(crate) fn place_hazards(the_game: &mut TheGame) {
+    let black_hole_frequency = the_game.game_defs.black_hole_frequency;
+    let nebula_frequency = the_game.game_defs.nebula_frequency;
+    for quadrant in the_game.quadrant_map.quadrants().collect::<Vec<_>>() {
+        if rnd(&mut the_game.rng) < black_hole_frequency {
+            the_game.quadrant_map[quadrant].black_holes = 1;
+        }
+        if rnd(&mut the_game.rng) < nebula_frequency {
+            the_game.quadrant_map[quadrant].nebulae =
+                1 + (rnd(&mut the_game.rng) * MAX_NEBULA_SECTORS as f64).floor() as i32;
+        }
+    }
+}
+
+/// How far the phasers reach in the current quadrant
+pub(crate) fn phaser_range(the_game: &TheGame) -> f64 {
+    if the_game.quadrant_map[the_game.quadrant].nebulae > 0 {
+        NEBULA_PHASER_RANGE
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// A black hole next to this sector that pulls in a ship passing through it, if one does
+pub(crate) fn black_hole_pull(the_game: &mut TheGame, sector: Sector) -> Option<Sector> {
+    let black_hole = the_game.sector_map.sectors().find(|&other| {
+        the_game.sector_map.sector_contents_at(other) == SectorContents::BlackHole
+            && fnd(other, sector) < 1.5
+    })?;
+    if rnd(&mut the_game.rng) < BLACK_HOLE_PULL_PROBABILITY {
+        Some(black_hole)
+    } else {
+        None
+    }
+}
+
+/// The Enterprise has fallen into a black hole, and the game is over
+pub(crate) fn swallow_enterprise(the_game: &mut TheGame, black_hole: Sector) {
+    the_game.swallowed = true;
+    the_game.game_state = GameState::Lost;
+    the_game.emit(GameEvent::SwallowedByBlackHole { sector: black_hole });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{new_game, Action, Ending, PhaserTargeting};
+    use crate::StResult;
+
+    #[test]
+    fn test_black_holes_and_nebulae() -> StResult<()> {
+        let mut the_game = new_game(3);
+        the_game.start()?;
+        the_game.damage.fix_damage();
+        let quadrant = the_game.quadrant;
+        assert!(!the_game.klingons.is_empty());
+
+        // A nebula keeps the phasers from reaching far
+        the_game.quadrant_map[quadrant].nebulae = 1;
+        let klingons = the_game.klingons.clone();
+        let events = the_game.apply(Action::Phasers {
+            energy: 100.0,
+            targeting: PhaserTargeting::Even,
+        })?;
+        for klingon in klingons.iter() {
+            let sector = klingon.sector();
+            let scattered = events.contains(&GameEvent::PhaserOutOfRange {
+                sector,
+                klingon: klingon.id(),
+            });
+            assert_eq!(fnd(sector, the_game.sector) > 4.0, scattered);
+        }
+
+        // and hides its quadrant from the long range sensors
+        let neighbour = the_game.quadrant_map.quadrant(
+            if quadrant.row() > 0 {
+                quadrant.row() - 1
+            } else {
+                1
+            },
+            quadrant.column(),
+        )?;
+        the_game.quadrant_map[neighbour].nebulae = 1;
+        match &the_game.apply(Action::LongRangeScan)?[..] {
+            [GameEvent::LongRangeScan { quadrants, .. }] => {
+                let row = (neighbour.row() - quadrant.row() + 1) as usize;
+                assert!(quadrants[row][1].unwrap().is_hidden());
+                assert!(!quadrants[1][1].unwrap().is_hidden());
+            }
+            events => panic!("expected a long range scan, got {:?}", events),
+        }
+
+        // A black hole right next to the Enterprise swallows torpedoes, and then the Enterprise
+        let sector = the_game
+            .sector_map
+            .sector(the_game.sector.row(), the_game.sector.column() + 1)?;
+        the_game.sector_map[sector] = SectorContents::BlackHole.into();
+        let events = the_game.apply(Action::Torpedo { course: 1.0 })?;
+        assert!(events.contains(&GameEvent::TorpedoSwallowed { sector }));
+        assert_eq!(
+            SectorContents::BlackHole,
+            the_game.sector_map.sector_contents_at(sector)
+        );
+        let events = the_game.apply(Action::Warp {
+            course: 1.0,
+            warp: 0.125,
+        })?;
+        assert!(events.contains(&GameEvent::SwallowedByBlackHole { sector }));
+        assert_eq!(GameState::Lost, the_game.game_state());
+        assert!(matches!(
+            events.last(),
+            Some(GameEvent::GameOver {
+                ending: Ending::SwallowedByBlackHole { .. },
+                ..
+            })
+        ));
+        Ok(())
+    }
+}
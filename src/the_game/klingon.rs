@@ -0,0 +1,131 @@
+//! # startrust::the_game::klingon
+
+use serde::{Deserialize, Serialize};
+
+use crate::the_game::phasers::fnd;
+use crate::the_game::{GameEvent, Sector, SectorContents};
+use crate::{StResult, TheGame};
+
+/// A Klingon battle cruiser in the current quadrant (was `k1`, `k2` and `k3`)
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Klingon {
+    /// Tells this ship apart from every other Klingon met in the game
+    id: usize,
+    sector: Sector,
+    shields: f64,
+}
+
+impl Klingon {
+    pub fn new(id: usize, sector: Sector, shields: f64) -> Self {
+        Self {
+            id,
+            sector,
+            shields,
+        }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn sector(&self) -> Sector {
+        self.sector
+    }
+
+    pub fn shields(&self) -> f64 {
+        self.shields
+    }
+
+    /// Take a hit (or, when firing, spend the energy) from the shields
+    pub(crate) fn drain_shields(&mut self, h: f64) {
+        self.shields -= h;
+    }
+
+    /// Put the Klingon in another sector (the sector map has to be kept up to date separately)
+    pub(crate) fn move_to(&mut self, sector: Sector) {
+        self.sector = sector;
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.shields <= 0.0
+    }
+}
+
+/// Let the Klingons manoeuvre after the Enterprise has acted. A Klingon with at least half of its
+/// shields left closes in, because hits are heavier from close up; one that has been worn down
+/// backs off. Each moves at most one sector, and only into empty space.
+pub fn move_klingons(the_game: &mut TheGame) -> StResult<()> {
+    let enterprise = the_game.sector;
+    let s9 = the_game.s9();
+    for i in 0..the_game.klingons.len() {
+        let from = the_game.klingons[i].sector();
+        let closing_in = the_game.klingons[i].shields() >= s9 / 2.0;
+        let mut to = from;
+        let mut distance = fnd(from, enterprise);
+        for row in (from.row() - 1)..=(from.row() + 1) {
+            for column in (from.column() - 1)..=(from.column() + 1) {
+                let sector = match the_game.sector_map.sector(row, column) {
+                    Ok(sector) => sector,
+                    Err(_) => continue,
+                };
+                if the_game.sector_map.sector_contents_at(sector) != SectorContents::Empty {
+                    continue;
+                }
+                let d = fnd(sector, enterprise);
+                if (closing_in && d < distance) || (!closing_in && d > distance) {
+                    to = sector;
+                    distance = d;
+                }
+            }
+        }
+        if to != from {
+            the_game.sector_map[from] = SectorContents::Empty.into();
+            the_game.sector_map[to] = SectorContents::Klingon.into();
+            the_game.klingons[i].move_to(to);
+            the_game.emit(GameEvent::KlingonMoved {
+                klingon: the_game.klingons[i].id(),
+                from,
+                to,
+            });
+        }
+    }
+    Ok(())
+} /* End moveklingons */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::new_game;
+
+    #[test]
+    fn test_klingons_move_through_empty_space() -> StResult<()> {
+        let mut the_game = new_game(7);
+        the_game.start()?;
+        let before = the_game.klingons.clone();
+        assert!(!before.is_empty());
+        move_klingons(&mut the_game)?;
+        let events = the_game.take_events();
+        for (klingon, moved) in before.iter().zip(the_game.klingons.iter()) {
+            let (from, to) = (klingon.sector(), moved.sector());
+            assert!((from.row() - to.row()).abs() <= 1 && (from.column() - to.column()).abs() <= 1);
+            assert_eq!(
+                SectorContents::Klingon,
+                the_game.sector_map.sector_contents_at(to)
+            );
+            if from != to {
+                assert_eq!(
+                    SectorContents::Empty,
+                    the_game.sector_map.sector_contents_at(from)
+                );
+                // Full shields, so it should have closed in
+                assert!(fnd(to, the_game.sector) < fnd(from, the_game.sector));
+                assert!(events.contains(&GameEvent::KlingonMoved {
+                    klingon: klingon.id(),
+                    from,
+                    to
+                }));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+//! # startrust::the_game::migration
+//!
+//! The Klingons' war, away from the Enterprise. As each stardate passes, groups of Klingons move
+//! between quadrants, often closing on a starbase, and worn-down ships slip away from the
+//! Enterprise. The galactic records only learn of it when the quadrants are seen again.
+
+use crate::the_game::{GameEvent, Quadrant, SectorContents};
+use crate::util::rnd;
+use crate::{StResult, TheGame};
+
+/// The most Klingons a quadrant can hold (all that its digit in the records can show)
+const MAX_QUADRANT_KLINGONS: i32 = 9;
+/// The chance that a worn-down Klingon escapes the Enterprise's quadrant each stardate
+const FLEE_PROBABILITY: f64 = 0.5;
+
+/// Move the Klingons around the galaxy as a stardate passes
+pub fn migrate_klingons(the_game: &mut TheGame) -> StResult<()> {
+    flee_enterprise(the_game)?;
+
+    // Decide where every group goes before moving any, so that none moves twice
+    let mut moves = Vec::new();
+    for quadrant in the_game.quadrant_map.quadrants() {
+        let contents = the_game.quadrant_map[quadrant];
+        if quadrant == the_game.quadrant || contents.klingons == 0 || contents.starbases > 0 {
+            // The Enterprise's quadrant is handled above, and a group at a starbase stays there
+            continue;
+        }
+        if rnd(&mut the_game.rng) > the_game.game_defs.migration_probability {
+            continue;
+        }
+        let to = if rnd(&mut the_game.rng) <= 0.5 {
+            match nearest_starbase(the_game, quadrant) {
+                Some(starbase) => step_towards(the_game, quadrant, starbase),
+                None => random_neighbour(the_game, quadrant),
+            }
+        } else {
+            random_neighbour(the_game, quadrant)
+        };
+        if let Some(to) = to {
+            // Only the group that is there now; any that arrive this stardate stay put
+            moves.push((quadrant, to, contents.klingons));
+        }
+    }
+
+    for (from, to, klingons) in moves {
+        if to == the_game.quadrant
+            || the_game.quadrant_map[to].klingons + klingons > MAX_QUADRANT_KLINGONS
+        {
+            continue;
+        }
+        the_game.quadrant_map[from].klingons -= klingons;
+        the_game.quadrant_map[to].klingons += klingons;
+    }
+    Ok(())
+} /* End migrateklingons */
+
+/// Worn-down Klingons in the Enterprise's quadrant may warp out to a neighbouring one
+fn flee_enterprise(the_game: &mut TheGame) -> StResult<()> {
+    let s9 = the_game.s9();
+    let current = the_game.quadrant;
+    let mut i = 0;
+    while i < the_game.klingons.len() {
+        let klingon = the_game.klingons[i];
+        if klingon.shields() >= s9 / 2.0 || rnd(&mut the_game.rng) > FLEE_PROBABILITY {
+            i += 1;
+            continue;
+        }
+        let to = match random_neighbour(the_game, current) {
+            Some(to) if the_game.quadrant_map[to].klingons < MAX_QUADRANT_KLINGONS => to,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        the_game.klingons.remove(i);
+        the_game.sector_map[klingon.sector()] = SectorContents::Empty.into();
+        the_game.quadrant_klingons -= 1;
+        the_game.quadrant_map[current].klingons -= 1;
+        the_game.quadrant_map[to].klingons += 1;
+        the_game.emit(GameEvent::KlingonFled {
+            klingon: klingon.id(),
+            sector: klingon.sector(),
+            to,
+        });
+    }
+    Ok(())
+}
+
+/// The closest quadrant with a starbase, counting diagonal steps as one
+fn nearest_starbase(the_game: &TheGame, from: Quadrant) -> Option<Quadrant> {
+    the_game
+        .quadrant_map
+        .quadrants()
+        .filter(|&quadrant| the_game.quadrant_map[quadrant].starbases > 0)
+        .min_by_key(|quadrant| {
+            (quadrant.row() - from.row())
+                .abs()
+                .max((quadrant.column() - from.column()).abs())
+        })
+}
+
+/// The next quadrant on the way from one quadrant to another
+fn step_towards(the_game: &TheGame, from: Quadrant, to: Quadrant) -> Option<Quadrant> {
+    the_game
+        .quadrant_map
+        .quadrant(
+            from.row() + (to.row() - from.row()).signum(),
+            from.column() + (to.column() - from.column()).signum(),
+        )
+        .ok()
+        .filter(|&quadrant| quadrant != from && !the_game.quadrant_map[quadrant].supernova)
+}
+
+/// Any quadrant next to this one, if the one picked is in the galaxy
+fn random_neighbour(the_game: &mut TheGame, from: Quadrant) -> Option<Quadrant> {
+    let row = from.row() + (rnd(&mut the_game.rng) * 3.0).floor() as i32 - 1;
+    let column = from.column() + (rnd(&mut the_game.rng) * 3.0).floor() as i32 - 1;
+    the_game
+        .quadrant_map
+        .quadrant(row, column)
+        .ok()
+        .filter(|&quadrant| quadrant != from && !the_game.quadrant_map[quadrant].supernova)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::TheGameDefsBuilder;
+
+    #[test]
+    fn test_klingon_migration_keeps_counts() -> StResult<()> {
+        let the_game_defs = TheGameDefsBuilder::default()
+            .seed(3)
+            .migration_probability(1.0)
+            .build()
+            .unwrap();
+        let mut the_game = TheGame::new(&the_game_defs);
+        the_game.start()?;
+        let galaxy_klingons = |the_game: &TheGame| {
+            the_game
+                .quadrant_map
+                .quadrants()
+                .map(|quadrant| the_game.quadrant_map[quadrant].klingons)
+                .sum::<i32>()
+        };
+        let total_klingons = the_game.total_klingons;
+        assert_eq!(total_klingons, galaxy_klingons(&the_game));
+        let before = the_game.records[the_game.quadrant];
+
+        for _ in 0..10 {
+            migrate_klingons(&mut the_game)?;
+            assert_eq!(total_klingons, galaxy_klingons(&the_game));
+            for quadrant in the_game.quadrant_map.quadrants() {
+                assert!(the_game.quadrant_map[quadrant].klingons <= 9);
+            }
+            assert_eq!(
+                the_game.quadrant_klingons,
+                the_game.quadrant_map[the_game.quadrant].klingons
+            );
+        }
+        // Nothing has been looked at, so the records haven't changed
+        assert_eq!(before, the_game.records[the_game.quadrant]);
+        assert!(the_game
+            .quadrant_map
+            .quadrants()
+            .any(|quadrant| the_game.quadrant_map[quadrant].klingons > 0
+                && the_game.records[quadrant].is_hidden()));
+        Ok(())
+    }
+}
@@ -1,43 +1,85 @@
 //! # startrust::the_game
 
-#[allow(unused_imports)]
-use std::io::{BufRead, Write};
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Read, Write};
 use std::str::FromStr;
 
 use log::debug;
 use num_enum::{FromPrimitive, IntoPrimitive};
+use serde::{Deserialize, Serialize};
 use strum_macros::{AsRefStr, EnumString};
 use termcolor::{Color, ColorSpec, WriteColor};
 use unwrap_infallible::UnwrapInfallible;
 
 use crate::error::StarTrustError::GameStateError;
-use crate::interaction::{getinp, InputValue};
-use crate::the_game::commands::Command;
-pub use crate::the_game::config::{TheGameDefs, TheGameDefsBuilder};
-use crate::the_game::damage::Damage;
-use crate::the_game::phasers::{fnd, phasers};
-use crate::the_game::quadrant::{setup_quadrant, Quadrant, QuadrantContents, QuadrantMap};
+use crate::interaction::{
+    beep, fgetline, get_computer_action, get_phasers_action, get_planet_action, get_probe_action,
+    get_shields_action, get_torpedo_action, get_warp_action, getinp, InputValue,
+};
+use crate::render::render_events;
+pub use crate::the_game::commands::{Action, Command};
+use crate::the_game::computer::{computer, computer_rejection};
+pub use crate::the_game::computer::{ComputerRequest, TorpedoSolution};
+pub use crate::the_game::config::{Difficulty, GameConfig, TheGameDefs, TheGameDefsBuilder};
+use crate::the_game::damage::{damage_report, damage_report_rejection, Damage, COMPONENTS};
+pub use crate::the_game::damage::{Component, ComponentStatus};
+pub use crate::the_game::events::{Ending, GameEvent, ShortRangeScan, Weapon};
+use crate::the_game::hazards::place_hazards;
+use crate::the_game::klingon::move_klingons;
+pub use crate::the_game::klingon::Klingon;
+use crate::the_game::nova::{schedule_supernova, SUPERNOVA_KILL_CREDIT};
+pub(crate) use crate::the_game::phasers::fnd;
+pub use crate::the_game::phasers::PhaserTargeting;
+use crate::the_game::phasers::{firing_distance, phasers, phasers_rejection};
+use crate::the_game::planets::{place_planets, planet, planet_rejection, LandingParty};
+pub use crate::the_game::planets::{LandingCraft, Planet, PlanetClass, PlanetOrder};
+use crate::the_game::probes::{launch_probe, probe_rejection};
+use crate::the_game::quadrant::{setup_quadrant, QuadrantMap};
+pub use crate::the_game::quadrant::{Quadrant, QuadrantContents};
+pub use crate::the_game::romulan::Romulan;
+use crate::the_game::romulan::{
+    place_romulans, romulans_act, romulans_hostile, UNPROVOKED_PENALTY,
+};
+use crate::the_game::save::{load_game, save_game};
 use crate::the_game::scan::{galactic_records, l_range_scan, s_range_scan};
 pub use crate::the_game::sector::{find_slot, Sector, SectorContents, SectorMap};
-use crate::the_game::stardate::StarDate;
-use crate::the_game::torpedoes::do_torpedoes;
-use crate::the_game::warp::do_warp;
-use crate::util::{get_random_x_y, gt, lt, rand_init, rnd};
+use crate::the_game::shields::{shields, shields_rejection, Shields};
+use crate::the_game::siege::{relieve_siege, Siege};
+pub use crate::the_game::stardate::StarDate;
+use crate::the_game::torpedoes::{do_spread, do_torpedoes, spread_rejection, torpedo_rejection};
+pub use crate::the_game::torpedoes::{fan, MAX_SPREAD};
+use crate::the_game::warp::{do_warp, warp_rejection};
+use crate::util::{get_random_x_y, gt, lt, new_seed, rand_init, rnd, GameRng};
 use crate::{yesno, StResult, StarTrustError};
 
 mod commands;
+mod computer;
 mod config;
 mod damage;
+mod events;
+mod hazards;
+mod klingon;
+mod migration;
+mod nova;
 mod path;
 mod phasers;
+mod planets;
+mod probes;
 mod quadrant;
+mod romulan;
+mod save;
 mod scan;
 mod sector;
+mod shields;
+mod siege;
 mod stardate;
 mod torpedoes;
 mod warp;
 
-#[derive(Copy, Clone, Debug, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
+#[derive(
+    Copy, Clone, Debug, Deserialize, IntoPrimitive, FromPrimitive, Eq, PartialEq, Serialize,
+)]
 #[repr(i32)]
 pub enum GameState {
     #[num_enum(default)]
@@ -48,7 +90,7 @@ pub enum GameState {
 }
 
 impl GameState {
-    fn is_done(&self) -> bool {
+    pub fn is_done(&self) -> bool {
         !matches!(self, GameState::InProgress)
     }
 
@@ -59,8 +101,8 @@ impl GameState {
     }
 }
 
-#[derive(AsRefStr, Debug, PartialEq, EnumString)]
-enum Condition {
+#[derive(AsRefStr, Copy, Clone, Debug, Deserialize, PartialEq, EnumString, Serialize)]
+pub enum Condition {
     #[strum(serialize = "RED")]
     Red,
     #[strum(serialize = "YELLOW")]
@@ -74,7 +116,7 @@ enum Condition {
 }
 
 impl Condition {
-    fn get_color_spec(&self) -> ColorSpec {
+    pub(crate) fn get_color_spec(&self) -> ColorSpec {
         match self {
             Condition::Red => {
                 let mut c = ColorSpec::new();
@@ -101,33 +143,30 @@ impl Condition {
     }
 }
 
-// The interpretation of (s1, s2) and (q1, q2) is unclear. Most of the code, the instructions, and
-// most of the game play suggest that they are (y, x), which is weird, but there are also places in
-// the code where they are used as if they are (x, y). It appears that (y, x) was the way it was
-// initially coded, but then someone modifying or translating the code, or even the author, became
-// confused and thought that it was (x, y). This rewrite preserves the confusion, for the time
-// being, because it is trying to recreate the 1978 game accurately. This is also why the ambiguous
-// names were preserved.
+// The original game kept the Enterprise's position in (s1, s2) and (q1, q2). The instructions and
+// the game play treat them as (row, column) -- (y, x) -- although parts of the original code used
+// them as if they were (x, y). `Sector` and `Quadrant` settle it: they are rows and columns.
 
+#[derive(Deserialize, Serialize)]
 pub struct TheGame {
     /// Current Energy
     energy: f64,
     /// Current Photon Torpedoes
     photo_torpedoes: i32,
+    /// Deep-space probes left
+    probes: i32,
     /// Current StarDate
     current_stardate: StarDate,
     /// Total remaining Klingons
     total_klingons: i32,
-    /// The Enterprise's x position within the Quadrant
-    s1: i32,
-    /// The Enterprise's y position within the Quadrant
-    s2: i32,
-    /// The x position of the current Quadrant
-    q1: i32,
-    /// The y position of the current Quadrant
-    q2: i32,
+    /// The Enterprise's position within the Quadrant (was `s1` and `s2`)
+    sector: Sector,
+    /// The current Quadrant (was `q1` and `q2`)
+    quadrant: Quadrant,
     /// The Damage Array
     damage: Damage,
+    /// The deflector shields
+    pub(crate) shields: Shields,
     /// Klingons Destroyed
     klingons_destroyed: i32,
     /// The number of Starbases
@@ -138,6 +177,8 @@ pub struct TheGame {
     pub(crate) sector_map: SectorMap,
     /// The Quadrant Map
     pub(crate) quadrant_map: QuadrantMap,
+    /// What the Enterprise last saw of each quadrant, which may since have changed
+    pub(crate) records: QuadrantMap,
     /// Configured game starting values
     pub(crate) game_defs: TheGameDefs,
     /// The current condition of the Enterprise
@@ -154,35 +195,85 @@ pub struct TheGame {
     course: f64,
     /// Warp
     warp: f64,
-    k1: Vec<i32>,
-    k2: Vec<i32>,
-    k3: Vec<f64>,
+    /// The Klingons still fighting in the current quadrant
+    klingons: Vec<Klingon>,
+    /// The id the next Klingon to appear will get
+    next_klingon_id: usize,
+    /// The Romulans in the current quadrant
+    romulans: Vec<Romulan>,
+    /// Whether the Romulans in the current quadrant have been attacked or rammed
+    romulans_provoked: bool,
+    /// Romulans destroyed while they were still neutral
+    unprovoked_romulan_kills: i32,
+    /// Whether the Enterprise is in orbit around the planet in the current quadrant
+    orbiting: bool,
+    /// Where the landing party is
+    landing_party: LandingParty,
+    /// Loads of dilithium crystals aboard
+    crystals: i32,
+    /// Whether the Enterprise fell into a black hole
+    swallowed: bool,
+    /// Whether the Enterprise was caught in a supernova
+    caught_in_supernova: bool,
+    /// Klingons lost to supernovas rather than to the Enterprise
+    supernova_kills: i32,
+    /// When a star somewhere in the galaxy is next due to go supernova (none if they don't)
+    next_supernova: Option<StarDate>,
+    /// The starbase under siege by the Klingons, if one is
+    siege: Option<Siege>,
+    /// Where starbases were destroyed by Klingon sieges
+    starbases_lost: Vec<Quadrant>,
     quadrant_stars: i32,
+    /// The seed this game's random numbers are generated from
+    seed: u64,
+    /// The game's random number generator
+    pub(crate) rng: GameRng,
+    /// Whether the game has been won or lost
+    game_state: GameState,
+    /// Whether the galaxy has been set up (a restored game has been)
+    started: bool,
+    /// What has happened since the last action, to be handed back to the frontend
+    #[serde(skip)]
+    pub(crate) events: Vec<GameEvent>,
 }
 
 impl TheGame {
     pub fn new(the_game_defs: &TheGameDefs) -> Self {
-        // s1 & s2 and q1 & q2 are not set initially, we will use (0, 0) the game
+        // The sector and quadrant are not set initially, we will use (0, 0) the game
         // initialization will randomize them later
         let b9 = 0;
         let c = 100_f64;
         let w = 10_f64;
+        let seed = the_game_defs.seed.unwrap_or_else(new_seed);
+        debug!("Game seed is {}", seed);
         Self {
             energy: the_game_defs.initial_energy,
             photo_torpedoes: the_game_defs.initial_photon_torpedoes,
+            probes: the_game_defs.initial_probes,
             current_stardate: the_game_defs.beginning_stardate,
             total_klingons: the_game_defs.initial_total_klingons,
-            sector_map: SectorMap::new(),
-            quadrant_map: QuadrantMap::new(),
-            s1: 0,
-            s2: 0,
-            q1: 0,
-            q2: 0,
+            sector_map: SectorMap::new(the_game_defs.quadrant_width, the_game_defs.quadrant_height),
+            quadrant_map: QuadrantMap::new(the_game_defs.galaxy_width, the_game_defs.galaxy_height),
+            records: QuadrantMap::new(the_game_defs.galaxy_width, the_game_defs.galaxy_height),
+            sector: Sector::default(),
+            quadrant: Quadrant::default(),
             damage: Damage::new(),
+            shields: Shields::default(),
             klingons_destroyed: 0,
-            k1: vec![0i32; 8],
-            k2: vec![0i32; 8],
-            k3: vec![0.0; 8],
+            klingons: Vec::new(),
+            next_klingon_id: 0,
+            romulans: Vec::new(),
+            romulans_provoked: false,
+            unprovoked_romulan_kills: 0,
+            orbiting: false,
+            landing_party: LandingParty::Aboard,
+            crystals: 0,
+            swallowed: false,
+            caught_in_supernova: false,
+            supernova_kills: 0,
+            next_supernova: None,
+            siege: None,
+            starbases_lost: Vec::new(),
             game_defs: *the_game_defs,
             total_starbases: b9,
             new_quadrant: false,
@@ -194,37 +285,40 @@ impl TheGame {
             saved_command: Command::Undefined, // the global version of `a`
             quadrant_stars: 0,
             ending_stardate: the_game_defs.ending_stardate,
+            seed,
+            rng: rand_init(seed, the_game_defs.random_mode),
+            game_state: GameState::InProgress,
+            started: false,
+            events: Vec::new(),
         }
     }
 
-    fn normalize_current_quadrant(&mut self) {
-        if self.q1 > 7 {
-            self.q1 = 7;
-        } else if self.q1 < 0 {
-            self.q1 = 0;
-        };
-        if self.q2 > 7 {
-            self.q2 = 7;
-        } else if self.q2 < 0 {
-            self.q2 = 0;
-        };
+    /// Record something that happened, to be returned from the current action
+    pub(crate) fn emit(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
     }
 
     /// Initialize
-    pub fn init<W: WriteColor>(&mut self, sout: &mut W) -> StResult<()> {
-        rand_init();
+    fn init(&mut self) -> StResult<()> {
         self.damage.fix_damage();
-        let (x, y) = get_random_x_y();
-        self.set_current_quadrant_from_coords(x, y);
+        let galaxy_width = self.quadrant_map.width();
+        let galaxy_height = self.quadrant_map.height();
+        let (x, y) = get_random_x_y(&mut self.rng, galaxy_width, galaxy_height);
+        self.quadrant = self.quadrant_map.quadrant(x, y)?;
 
-        let x = 8;
+        // Up to one star for every eight sectors, but no more than its digit in the records can show
+        let x = (self.sector_map.width() * self.sector_map.height() / 8).min(9);
         let y = 1;
         let mut total_starbases = self.total_starbases;
 
         let the_game_defs = self.game_defs;
         let mut ending_stardate = the_game_defs.ending_stardate;
         let beginning_stardate = self.beginning_stardate();
-        let mut total_klingons = self.total_klingons as i32;
+        let mut total_klingons = self.total_klingons;
         let x1 = self.game_defs.x1;
         let x2 = self.game_defs.x2;
         let y1 = self.game_defs.y1;
@@ -232,42 +326,43 @@ impl TheGame {
         let mut klingons = self.quadrant_klingons;
         let mut starbases;
 
-        for i in 0..8 {
-            for j in 0..8 {
+        for i in 0..galaxy_height {
+            for j in 0..galaxy_width {
                 klingons = 0;
-                let mut n = rnd();
+                let mut n = rnd(&mut self.rng);
                 if n < x1 {
                     n *= 64.0;
-                    klingons = lt(n, y1) as i32 - (y as i32);
+                    klingons = lt(n, y1) - y;
                     klingons = -(klingons
-                        + lt(n, x2) as i32
-                        + lt(n, y2) as i32
-                        + lt(n, 0.08) as i32
-                        + lt(n, 0.03) as i32
-                        + lt(n, 0.01) as i32);
-                    total_klingons += klingons as i32;
+                        + lt(n, x2)
+                        + lt(n, y2)
+                        + lt(n, 0.08)
+                        + lt(n, 0.03)
+                        + lt(n, 0.01));
+                    total_klingons += klingons;
                 }
 
-                starbases = -gt(rnd(), self.game_defs.starbase_frequency);
+                starbases = -gt(rnd(&mut self.rng), self.game_defs.starbase_frequency);
                 total_starbases += starbases;
 
-                let stars = (rnd() * (x as f64) + (y as f64)).floor() as i32;
+                let stars = (rnd(&mut self.rng) * (x as f64) + (y as f64)).floor() as i32;
 
-                let quadrant = Quadrant::new(i, j);
+                let quadrant = self.quadrant_map.quadrant(i, j)?;
                 self.quadrant_map[quadrant] =
                     QuadrantContents::new(klingons, starbases, stars, true);
             }
         }
 
         // Ensure that there are not more Klignons than years
-        if total_klingons > (ending_stardate - beginning_stardate) as i32 {
-            ending_stardate = beginning_stardate + total_klingons as i32;
+        if total_klingons > (ending_stardate - beginning_stardate) {
+            ending_stardate = beginning_stardate + total_klingons;
         }
 
         // Ensure that there is at least one starbase
         if total_starbases <= 0 {
-            let (starbase_x, starbase_y) = get_random_x_y();
-            let quadrant = Quadrant::new(starbase_x, starbase_y);
+            let (starbase_x, starbase_y) =
+                get_random_x_y(&mut self.rng, galaxy_width, galaxy_height);
+            let quadrant = self.quadrant_map.quadrant(starbase_x, starbase_y)?;
             let mut quadrant_value = self.quadrant_map[quadrant];
             debug!(
                 "About to add one to quadrant {} with value {:?}",
@@ -282,14 +377,16 @@ impl TheGame {
             self.quadrant_map[quadrant] = quadrant_value;
             total_starbases = 1;
         }
+        place_romulans(self);
+        place_planets(self);
+        place_hazards(self);
+        schedule_supernova(self);
 
-        let years = ending_stardate - beginning_stardate;
-        writeln!(
-            sout,
-            "OBJECTIVE: DESTROY {} KLINGON BATTLE CRUISERS IN {} YEARS.",
-            total_klingons, years
-        )?;
-        writeln!(sout, " THE NUMBER OF STARBASES IS {}.\n", total_starbases)?;
+        self.emit(GameEvent::GameStarted {
+            klingons: total_klingons,
+            years: ending_stardate - beginning_stardate,
+            starbases: total_starbases,
+        });
 
         self.quadrant_klingons = klingons;
         self.total_klingons = total_klingons;
@@ -305,73 +402,41 @@ impl TheGame {
         self.ending_stardate - self.beginning_stardate()
     }
 
-    pub fn increment_year(&mut self) {
-        self.current_stardate += 1i32;
-    }
-
-    fn current_sector(&self) -> Sector {
-        Sector::new(self.s1, self.s2)
-    }
-
-    fn set_current_sector(&mut self, sector: Sector) {
-        self.set_current_sector_from_coords(sector.x(), sector.y())
-    }
-
-    fn set_current_sector_from_coords(&mut self, x: i32, y: i32) {
-        self.s1 = x;
-        self.s2 = y;
-    }
-
-    fn current_quadrant(&self) -> Quadrant {
-        Quadrant::new(self.q1, self.q2)
-    }
-
-    #[allow(dead_code)]
-    fn set_current_quadrant(&mut self, quadrant: Quadrant) {
-        self.set_current_quadrant_from_coords(quadrant.x(), quadrant.y());
-    }
-
-    fn set_current_quadrant_from_coords(&mut self, x: i32, y: i32) {
-        self.q1 = x;
-        self.q2 = y;
+    /// Note what is in a quadrant now, for the galactic records
+    pub(crate) fn record_quadrant(&mut self, quadrant: Quadrant) {
+        self.records[quadrant] = self.quadrant_map[quadrant].as_seen();
     }
 
-    fn is_current_quadrant(&self, x: i32, y: i32) -> bool {
-        x == self.q1 && y == self.q2
+    pub fn increment_year(&mut self) {
+        self.current_stardate += 1i32;
     }
 
-    /// Display current star date
-    pub fn show_stardate<W: WriteColor>(&self, sout: &mut W) -> StResult<()> {
-        write!(sout, "\nIT IS STARDATE {}.\n", self.current_stardate)?;
-        sout.flush().map_err(|e| {
-            let e: StarTrustError = e.into();
-            e
-        })
-    } /* End showstardate */
-
     /// Check condition
     fn check_condition(&mut self) {
-        let s1 = self.s1 as i32;
-        let s2 = self.s2 as i32;
+        let row = self.sector.row();
+        let column = self.sector.column();
         let e0 = self.game_defs.initial_energy;
         let p0 = self.game_defs.initial_photon_torpedoes;
-        for i in (s1 - 1)..=(s1 + 1) {
-            for j in (s2 - 1)..=(s2 + 1) {
-                if (i >= 0) && (i <= 7) && (j >= 0) && (j <= 7) {
-                    let sector = Sector::new(i as i32, j as i32);
-                    if self.sector_map[sector] == SectorContents::Starbase.into() {
+        for i in (row - 1)..=(row + 1) {
+            for j in (column - 1)..=(column + 1) {
+                if let Ok(sector) = self.sector_map.sector(i, j) {
+                    if self.sector_map[sector] == i32::from(SectorContents::Starbase) {
                         // Docked at starbase
+                        if !self.is_docked() {
+                            self.emit(GameEvent::Docked);
+                        }
                         self.current_condition = Condition::Docked;
                         self.energy = e0;
                         self.photo_torpedoes = p0;
+                        self.probes = self.game_defs.initial_probes;
                         self.damage.fix_damage();
                         return;
                     }
                 }
             }
         }
-        if self.quadrant_klingons > 0 {
-            // Klingons present!
+        if self.quadrant_klingons > 0 || romulans_hostile(self) {
+            // Klingons (or angry Romulans) present!
             self.current_condition = Condition::Red;
         } else if self.energy < (0.1 * e0) {
             // Low energy
@@ -382,55 +447,89 @@ impl TheGame {
         }
     } /* End checkcond */
 
-    /// Show hit on Enterprise or Klingon
-    fn show_hit<W: WriteColor>(
-        &self,
-        sout: &mut W,
-        i: usize,
-        es: &str,
-        n: f64,
-        h: f64,
-    ) -> StResult<()> {
-        writeln!(
-            sout,
-            "{:.3} UNIT HIT ON {} SECTOR {} - {}  ({:.3} LEFT)",
-            h,
-            es,
-            self.k1[i] + 1,
-            self.k2[i] + 1,
-            n
-        )
-        .map_err(|e| e.into())
-    } /* End showhit */
-
     fn is_docked(&self) -> bool {
         // This is an amazingly stupid way to do this, but it's how they do it
         self.current_condition == Condition::Docked
     }
 
-    /// Check for hits from Klingons
-    fn check_for_hits<W: WriteColor>(&mut self, sout: &mut W) -> StResult<()> {
-        if self.quadrant_klingons < 1 {
+    /// Check for hits from Klingons, and from any Romulans that have been provoked
+    fn check_for_hits(&mut self) -> StResult<()> {
+        if self.quadrant_klingons < 1 && !romulans_hostile(self) {
             /* No Klingons here! */
             return Ok(());
         }
         if self.is_docked() {
-            writeln!(sout, "STARBASE PROTECTS ENTERPRISE.")?;
+            self.emit(GameEvent::StarbaseProtects);
             return Ok(());
         }
-        for i in 0..8 {
-            if self.k3[i] > 0.0 {
-                let mut h = self.k3[i] * 0.4 * rnd();
-                self.k3[i] -= h;
-                h /= fnd(self.k1[i], self.k2[i], self.s1, self.s2).powf(0.4);
-                self.energy -= h;
-                let n: f64 = self.energy;
-                self.show_hit(sout, i, "ENTERPRISE FROM", n, h)?;
+        for i in 0..self.klingons.len() {
+            let klingon = &mut self.klingons[i];
+            let mut h = klingon.shields() * 0.4 * rnd(&mut self.rng);
+            klingon.drain_shields(h);
+            let klingon = *klingon;
+            h /= firing_distance(klingon.sector(), self.sector).powf(0.4);
+            let through = self.shields.absorb(h);
+            self.energy -= through;
+            self.emit(GameEvent::EnterpriseHit {
+                from: klingon.sector(),
+                klingon: klingon.id(),
+                hit: h,
+                energy_left: self.energy,
+                shields_left: self.shields_left(),
+            });
+            self.hit_damage(through)?;
+        }
+        if romulans_hostile(self) {
+            for i in 0..self.romulans.len() {
+                let romulan = &mut self.romulans[i];
+                let mut h = romulan.shields() * 0.4 * rnd(&mut self.rng);
+                romulan.drain_shields(h);
+                let from = romulan.sector();
+                h /= firing_distance(from, self.sector).powf(0.4);
+                let through = self.shields.absorb(h);
+                self.energy -= through;
+                self.emit(GameEvent::EnterpriseHitByRomulan {
+                    from,
+                    hit: h,
+                    energy_left: self.energy,
+                    shields_left: self.shields_left(),
+                });
+                self.hit_damage(through)?;
             }
         }
         Ok(())
     } /* End checkforhits */
 
+    /// What is left in the shields, if they are up
+    fn shields_left(&self) -> Option<f64> {
+        if self.shields.is_up() {
+            Some(self.shields.energy())
+        } else {
+            None
+        }
+    }
+
+    /// What gets past the shields can knock out a component, and more so with them lowered
+    fn hit_damage(&mut self, through: f64) -> StResult<()> {
+        let chance = through / self.s9() * if self.shields.is_up() { 1.0 } else { 2.0 };
+        if through > 0.0 && rnd(&mut self.rng) < chance {
+            let x = (rnd(&mut self.rng) * COMPONENTS as f64).floor() as usize;
+            self.damage
+                .add_damage(x, 1 + (rnd(&mut self.rng) * 3.0).floor() as i32);
+            let component: Component = x.try_into()?;
+            self.emit(GameEvent::HitDamage {
+                component,
+                repair_time: self.damage.get_damage(component),
+            });
+        }
+        Ok(())
+    }
+
+    /// The seed that reproduces this game
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     pub fn s9(&self) -> f64 {
         self.game_defs.s9
     }
@@ -439,158 +538,402 @@ impl TheGame {
         self.game_defs.beginning_stardate
     }
 
-    pub fn play<R: BufRead, W: WriteColor>(&mut self, sin: &mut R, sout: &mut W) -> StResult<()> {
-        let mut gamecomp = GameState::InProgress;
-        let mut moved: bool = false;
-        let mut command = self.saved_command;
-
-        debug!(
-            "Init gamecomp={:?}, moved={}, command={:?}",
-            gamecomp, moved, command
-        );
-        self.init(sout)?;
+    /// How many photon torpedoes are left
+    pub fn photon_torpedoes(&self) -> i32 {
+        self.photo_torpedoes
+    }
+
+    /// Where the Enterprise is in the current quadrant
+    pub fn sector(&self) -> Sector {
+        self.sector
+    }
+
+    /// Loads of dilithium crystals aboard
+    pub fn crystals(&self) -> i32 {
+        self.crystals
+    }
+
+    /// The Klingons in the current quadrant, for the frontend to aim at
+    pub fn klingons(&self) -> &[Klingon] {
+        &self.klingons
+    }
+
+    /// Whether the game is still going, or how it ended
+    pub fn game_state(&self) -> GameState {
+        self.game_state
+    }
+
+    /// Set up the galaxy and place the Enterprise, returning what the player sees first
+    pub fn start(&mut self) -> StResult<Vec<GameEvent>> {
+        debug!("Starting game, game_state={:?}", self.game_state);
+        if self.started {
+            // A restored game picks up where it was saved
+            s_range_scan(self, Command::ShortRangeScan.into())?;
+            return Ok(self.take_events());
+        }
+        self.started = true;
+        self.init()?;
         self.new_quadrant = true;
-        debug!(
-            "Done initing gamecomp={:?}, moved={}, command={:?}, newquad={}",
-            gamecomp, moved, command, self.new_quadrant
-        );
-
-        while !gamecomp.is_done() {
-            if self.new_quadrant {
-                setup_quadrant(self);
-                command = self.saved_command;
-            }
-            self.new_quadrant = false;
-            moved = false;
-            s_range_scan(self, sout, command.into())?;
-            if self.energy <= 0.0 {
-                /* Ran out of energy */
-                gamecomp = GameState::Lost;
-            } else {
-                loop
-                /* Command loop (-99 or ESC to quit) */
-                {
-                    write!(sout, "COMMAND? ")?;
-                    sout.flush()?;
-                    let ebuff = getinp(sin, sout, 7, 2.into())?;
-                    writeln!(sout)?;
-                    match ebuff {
-                        InputValue::Blank => command = Command::Undefined,
-                        InputValue::Esc => command = (-99).into(),
-                        InputValue::InputString(cmdbuff) => {
-                            command = Command::from_str(cmdbuff.as_str()).unwrap_infallible();
-                        }
-                    }
-                    match command {
-                        Command::WarpEngines => {
-                            //case 1 :
-                            // Warp engines
-                            do_warp(self, sin, sout, &mut command, &mut gamecomp, &mut moved)?;
-                        }
-                        Command::ShortRangeScan => {
-                            //case 2 :
-                            // Short-range scan
-                            s_range_scan(self, sout, command.into())?;
-                        }
-                        Command::LongRangeScan => {
-                            //case 3 :
-                            /* Long-range scan */
-                            l_range_scan(self, sout)?;
-                        }
-                        Command::Phasers => {
-                            //case 4 :
-                            /* Phasers */
-                            let x = phasers(self, sin, sout)?;
-                            gamecomp.update(x);
-                        }
-                        Command::PhotonTorpedos => {
-                            //case 5 :
-                            // Photon torpedoes
-                            do_torpedoes(self, sin, sout, &mut command, &mut gamecomp)?;
-                        }
-                        Command::GalacticRecords => {
-                            //case 6 :
-                            /* Galactic records */
-                            galactic_records(self, sout)?;
-                        }
-                        Command::Quit => {
-                            write!(sout, "\nARE YOU SURE YOU WANT TO QUIT? ")?;
-                            sout.flush()?;
-                            let ans = yesno(sin)?;
-                            if ans == 'Y' {
-                                gamecomp = (-99).into();
-                                break; /* Break out of command loop */
-                            } else {
-                                continue;
-                            } /* Back to top of command loop */
-                        }
-                        Command::Undefined => {
-                            debug!("undefined command in command loop.");
-                            for i in 1..7 {
-                                let command: Command = i.into();
-                                writeln!(sout, "  {} = {}", i, command)?;
-                            }
-                            writeln!(sout, "  -99 OR ESC TO QUIT\n")?;
-                            // Back to top of command loop
-                            continue;
-                        }
-                    }
+        self.arrive()?;
+        if self.game_state.is_done() {
+            self.game_over()?;
+        }
+        Ok(self.take_events())
+    }
 
-                    if gamecomp.is_done() {
-                        break;
-                    }
-                    if moved {
-                        // Enterprise moved
-                        break;
-                    }
-                } /* End command loop */
+    /// Explain why an action can't be carried out, if it can't. An empty explanation means that the
+    /// action is fine.
+    pub fn validate(&self, action: &Action) -> Vec<GameEvent> {
+        match *action {
+            Action::Warp { course, warp } => warp_rejection(self, course, warp),
+            Action::Phasers {
+                energy,
+                ref targeting,
+            } => phasers_rejection(self, energy, targeting),
+            Action::Torpedo { course } => torpedo_rejection(self, course),
+            Action::TorpedoSpread { ref courses } => spread_rejection(self, courses),
+            Action::Shields { transfer, .. } => shields_rejection(self, transfer),
+            Action::DamageReport => damage_report_rejection(self),
+            Action::Computer(_) => computer_rejection(self),
+            Action::Probe { course } => probe_rejection(self, course),
+            Action::Planet(order) => planet_rejection(self, order),
+            Action::ShortRangeScan
+            | Action::LongRangeScan
+            | Action::GalacticRecords
+            | Action::Quit => Vec::new(),
+        }
+    }
+
+    /// Carry out one of the captain's orders, and return everything that happened as a result. An
+    /// action that fails `validate` changes nothing, and only returns the explanation.
+    pub fn apply(&mut self, action: Action) -> StResult<Vec<GameEvent>> {
+        if self.game_state.is_done() {
+            return Err(GameStateError(format!(
+                "{:?} applied after the game was over",
+                action
+            )));
+        }
+        let rejection = self.validate(&action);
+        if !rejection.is_empty() {
+            return Ok(rejection);
+        }
+        let quadrant = self.quadrant;
+        // Whether the Klingons get to answer the action (scans take no time, and aborted orders
+        // don't count)
+        let took_turn = match action {
+            Action::Warp { course, warp } => {
+                let moved = do_warp(self, course, warp)?;
+                if moved {
+                    // Enterprise moved, and out of any orbit
+                    self.orbiting = false;
+                    self.arrive()?;
+                }
+                moved
             }
-        } /* Game is over! */
+            Action::ShortRangeScan => {
+                s_range_scan(self, Command::ShortRangeScan.into())?;
+                false
+            }
+            Action::LongRangeScan => {
+                l_range_scan(self)?;
+                false
+            }
+            Action::Phasers { energy, targeting } => {
+                let x = phasers(self, energy, &targeting)?;
+                self.game_state.update(x);
+                energy > 0.0
+            }
+            Action::Torpedo { course } => {
+                do_torpedoes(self, course)?;
+                course >= 1.0
+            }
+            Action::TorpedoSpread { courses } => {
+                do_spread(self, &courses)?;
+                true
+            }
+            Action::GalacticRecords => {
+                galactic_records(self)?;
+                false
+            }
+            Action::Shields { up, transfer } => {
+                shields(self, up, transfer)?;
+                false
+            }
+            Action::DamageReport => {
+                damage_report(self)?;
+                false
+            }
+            Action::Computer(request) => {
+                computer(self, request)?;
+                false
+            }
+            Action::Probe { course } => {
+                launch_probe(self, course)?;
+                false
+            }
+            Action::Planet(order) => planet(self, order)?,
+            Action::Quit => {
+                self.game_state.update(GameState::Quit);
+                false
+            }
+        };
+        if self.new_quadrant && !self.game_state.is_done() {
+            // Thrown clear of a supernova
+            self.arrive()?;
+        }
+        // Klingons in a quadrant the Enterprise has just entered have only now been placed
+        if took_turn && !self.game_state.is_done() && self.quadrant == quadrant {
+            move_klingons(self)?;
+            romulans_act(self)?;
+        }
+        if self.game_state.is_done() {
+            self.game_over()?;
+        }
+        Ok(self.take_events())
+    }
 
-        self.show_stardate(sout)?;
-        match gamecomp {
+    /// Show the quadrant that the Enterprise has just moved into, or within
+    fn arrive(&mut self) -> StResult<()> {
+        if self.new_quadrant {
+            setup_quadrant(self)?;
+            relieve_siege(self);
+        }
+        self.new_quadrant = false;
+        let command = self.saved_command;
+        s_range_scan(self, command.into())?;
+        if self.energy <= 0.0 {
+            /* Ran out of energy */
+            self.game_state.update(GameState::Lost);
+        }
+        Ok(())
+    }
+
+    /// Report how the game ended
+    fn game_over(&mut self) -> StResult<()> {
+        let ending = match self.game_state {
             GameState::Won => {
                 let t = self.current_stardate;
                 let t0 = self.beginning_stardate();
                 let drate: f64 = (t - t0) as f64;
-                let rating: i32 = ((self.klingons_destroyed as f64 / drate) * 1000.0) as i32;
-                writeln!(sout, "THE FEDERATION HAS BEEN SAVED!")?;
-                writeln!(sout, "YOU ARE PROMOTED TO ADMIRAL.")?;
-                writeln!(
-                    sout,
-                    "{} KLINGONS IN {} YEARS.  RATING = {}\n",
-                    self.klingons_destroyed,
-                    t - t0,
+                // Klingons the Enterprise didn't destroy itself only count for part
+                let credited = (self.klingons_destroyed - self.supernova_kills) as f64
+                    + self.supernova_kills as f64 * SUPERNOVA_KILL_CREDIT;
+                let rating: i32 =
+                    ((credited / drate) * 1000.0 * self.game_defs.difficulty.rating_factor())
+                        as i32
+                        - self.unprovoked_romulan_kills * UNPROVOKED_PENALTY;
+                Ending::Won {
+                    klingons_destroyed: self.klingons_destroyed,
+                    years: t - t0,
                     rating,
-                )?;
+                    unprovoked_romulan_kills: self.unprovoked_romulan_kills,
+                    supernova_kills: self.supernova_kills,
+                }
             }
             GameState::Lost => {
-                if self.current_stardate > self.game_defs.ending_stardate {
-                    writeln!(sout, "YOU RAN OUT OF TIME!")?;
+                let klingons_left = self.total_klingons;
+                if self.swallowed {
+                    Ending::SwallowedByBlackHole { klingons_left }
+                } else if self.caught_in_supernova {
+                    Ending::CaughtInSupernova { klingons_left }
+                } else if self.current_stardate > self.game_defs.ending_stardate {
+                    Ending::RanOutOfTime { klingons_left }
                 } else if self.energy <= 0.0 {
-                    writeln!(sout, "YOU RAN OUT OF ENERGY!")?;
+                    Ending::RanOutOfEnergy { klingons_left }
                 } else {
                     return Err(GameStateError(String::from(
                         "GameState::Lost with no discernible reason",
                     )));
                 }
-                writeln!(sout, "THANKS TO YOUR BUNGLING, THE FEDERATION WILL BE")?;
-                writeln!(
-                    sout,
-                    "CONQUERED BY THE REMAINING {} KLINGON CRUISERS!",
-                    self.total_klingons
-                )?;
-                writeln!(sout, "YOU ARE DEMOTED TO CABIN BOY!")?;
-            }
-            GameState::Quit => {
-                writeln!(sout, "OKAY, QUITTER -- NO KUDOS FOR YOU.")?;
             }
+            GameState::Quit => Ending::Quit,
             GameState::InProgress => {
                 return Err(StarTrustError::GameStateError(String::from(
                     "`gamecomp` is `InProgress`, but in game complete",
                 )))
             }
+        };
+        self.emit(GameEvent::GameOver {
+            stardate: self.current_stardate,
+            ending,
+            starbases_lost: self.starbases_lost.clone(),
+        });
+        Ok(())
+    }
+
+    /// Save the whole state of the game, to be picked up again with `TheGame::load`
+    pub fn save<W: Write>(&self, writer: W) -> StResult<()> {
+        save_game(self, writer)
+    }
+
+    /// Restore a saved game. `start` resumes it rather than setting up a new galaxy.
+    pub fn load<R: Read>(reader: R) -> StResult<Self> {
+        load_game(reader)
+    }
+
+    /// Ask for a file name and save the game there
+    fn save_to_file<R: BufRead, W: WriteColor>(&self, sin: &mut R, sout: &mut W) -> StResult<()> {
+        write!(sout, "FILE NAME? ")?;
+        sout.flush()?;
+        let file_name = fgetline(sin)?;
+        if file_name.is_empty() {
+            return Ok(());
+        }
+        match File::create(&file_name)
+            .map_err(StarTrustError::from)
+            .and_then(|file| self.save(BufWriter::new(file)))
+        {
+            Ok(()) => writeln!(sout, "GAME SAVED TO {}.\n", file_name)?,
+            Err(e) => {
+                beep();
+                writeln!(sout, "UNABLE TO SAVE GAME: {}\n", e)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Play a game on the terminal
+    pub fn play<R: BufRead, W: WriteColor>(&mut self, sin: &mut R, sout: &mut W) -> StResult<()> {
+        self.play_stepwise(sin, sout, |_| Ok(()))
+    }
+
+    /// Play a game on the terminal, calling `before_command` whenever the game is about to ask for
+    /// the next command
+    pub fn play_stepwise<R, W, F>(
+        &mut self,
+        sin: &mut R,
+        sout: &mut W,
+        mut before_command: F,
+    ) -> StResult<()>
+    where
+        R: BufRead,
+        W: WriteColor,
+        F: FnMut(&mut W) -> StResult<()>,
+    {
+        let events = self.start()?;
+        render_events(sout, &events)?;
+
+        while !self.game_state.is_done() {
+            /* Command loop (-99 or ESC to quit) */
+            before_command(sout)?;
+            write!(sout, "COMMAND? ")?;
+            sout.flush()?;
+            let ebuff = getinp(sin, sout, 7, 2.into())?;
+            writeln!(sout)?;
+            let command = match ebuff {
+                InputValue::Blank => Command::Undefined,
+                InputValue::Esc => (-99).into(),
+                InputValue::InputString(cmdbuff) => {
+                    Command::from_str(cmdbuff.as_str()).unwrap_infallible()
+                }
+            };
+            let action = match command {
+                Command::WarpEngines => get_warp_action(self, sin, sout)?,
+                Command::ShortRangeScan => Some(Action::ShortRangeScan),
+                Command::LongRangeScan => Some(Action::LongRangeScan),
+                Command::Phasers => get_phasers_action(self, sin, sout)?,
+                Command::PhotonTorpedos => get_torpedo_action(self, sin, sout)?,
+                Command::GalacticRecords => Some(Action::GalacticRecords),
+                Command::Shields => get_shields_action(self, sin, sout)?,
+                Command::DamageReport => Some(Action::DamageReport),
+                Command::Computer => get_computer_action(self, sin, sout)?,
+                Command::Probe => get_probe_action(self, sin, sout)?,
+                Command::Planets => get_planet_action(sin, sout)?,
+                Command::Save => {
+                    self.save_to_file(sin, sout)?;
+                    None
+                }
+                Command::Quit => {
+                    write!(sout, "\nARE YOU SURE YOU WANT TO QUIT? ")?;
+                    sout.flush()?;
+                    let ans = yesno(sin)?;
+                    if ans == 'Y' {
+                        Some(Action::Quit)
+                    } else {
+                        None
+                    }
+                }
+                Command::Undefined => {
+                    debug!("undefined command in command loop.");
+                    for i in 1..13 {
+                        let command: Command = i.into();
+                        writeln!(sout, "  {} = {}", i, command)?;
+                    }
+                    writeln!(sout, "  -99 OR ESC TO QUIT\n")?;
+                    None
+                }
+            };
+            if let Some(action) = action {
+                let events = self.apply(action)?;
+                render_events(sout, &events)?;
+            }
+        } /* Game is over! */
+        Ok(())
+    }
+}
+
+/// A game from the default definitions with this seed, not yet started, for the tests
+#[cfg(test)]
+pub(crate) fn new_game(seed: u64) -> TheGame {
+    let the_game_defs = TheGameDefsBuilder::default().seed(seed).build().unwrap();
+    TheGame::new(&the_game_defs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_shows_short_range_scan() -> StResult<()> {
+        let mut the_game = new_game(1);
+        let events = the_game.start()?;
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, GameEvent::ShortRangeScan(_))));
+        assert_eq!(GameState::InProgress, the_game.game_state());
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_seed_same_events() -> StResult<()> {
+        let actions = [
+            Action::LongRangeScan,
+            Action::Warp {
+                course: 4.5,
+                warp: 1.0,
+            },
+            Action::Phasers {
+                energy: 100.0,
+                targeting: PhaserTargeting::Even,
+            },
+            Action::Torpedo { course: 3.0 },
+            Action::GalacticRecords,
+        ];
+        let mut game_a = new_game(1978);
+        let mut game_b = new_game(1978);
+        assert_eq!(game_a.start()?, game_b.start()?);
+        for action in actions.iter() {
+            if game_a.game_state().is_done() {
+                break;
+            }
+            assert_eq!(game_a.apply(action.clone())?, game_b.apply(action.clone())?);
         }
         Ok(())
     }
+
+    #[test]
+    fn test_rejected_action_changes_nothing() -> StResult<()> {
+        let mut the_game = new_game(3);
+        the_game.start()?;
+        // Whatever hit the Enterprise on the way in, the phasers should be working
+        the_game.damage.fix_damage();
+        let energy = the_game.energy;
+        let events = the_game.apply(Action::Phasers {
+            energy: energy + 1.0,
+            targeting: PhaserTargeting::Even,
+        })?;
+        assert_eq!(vec![GameEvent::NotEnoughEnergy { energy }], events);
+        assert_eq!(energy, the_game.energy);
+        Ok(())
+    }
 }
@@ -0,0 +1,426 @@
+//! # startrust::the_game::nova
+//!
+//! A star hit by a photon torpedo goes nova. The blast batters whatever is in the sectors around
+//! it, pushing ships away if there is room, and sets off any star next to it in turn. Very rarely
+//! the star goes supernova instead, which destroys everything in the quadrant and leaves it
+//! uninhabitable. The shock wave throws the Enterprise clear into a neighbouring quadrant, unless
+//! there is nowhere left to go.
+//!
+//! Stars elsewhere in the galaxy go supernova on their own from time to time, and Starfleet marks
+//! them in the galactic records. The remains can't be entered for some years: the navigation
+//! computer drops the Enterprise out of warp short of them, if it is working.
+
+use crate::the_game::damage::Component;
+use crate::the_game::{
+    GameEvent, GameState, LandingParty, Quadrant, QuadrantContents, Sector, SectorContents, Weapon,
+};
+use crate::util::rnd;
+use crate::{StResult, TheGame};
+
+const COMPUTER: Component = Component::GalacticRecords; // Component #5
+
+/// The chance that a star hit by a torpedo goes supernova instead of nova
+const SUPERNOVA_PROBABILITY: f64 = 0.05;
+/// The hit a nova deals to each ship next to it
+const NOVA_HIT: f64 = 150.0;
+/// The hit the Enterprise takes riding out a supernova's shock wave
+const SUPERNOVA_HIT: f64 = 500.0;
+/// How long the remains of a supernova can't be entered, in years
+const IMPASSABLE_YEARS: i32 = 5;
+/// What each Klingon lost to a supernova counts for in the rating, against one the Enterprise
+/// destroyed
+pub(crate) const SUPERNOVA_KILL_CREDIT: f64 = 0.5;
+
+/// Set off a star, and any stars that its blast reaches in turn
+pub(crate) fn nova(the_game: &mut TheGame, star: Sector) -> StResult<()> {
+    if rnd(&mut the_game.rng) < SUPERNOVA_PROBABILITY {
+        return supernova(the_game, the_game.quadrant);
+    }
+    let mut stars = vec![star];
+    while let Some(star) = stars.pop() {
+        the_game.sector_map[star] = SectorContents::Empty.into();
+        the_game.quadrant_stars -= 1;
+        the_game.emit(GameEvent::Nova { sector: star });
+        for row in (star.row() - 1)..=(star.row() + 1) {
+            for column in (star.column() - 1)..=(star.column() + 1) {
+                let sector = match the_game.sector_map.sector(row, column) {
+                    Ok(sector) => sector,
+                    Err(_) => continue,
+                };
+                // Ships are pushed straight away from the star
+                let away = the_game
+                    .sector_map
+                    .sector(2 * row - star.row(), 2 * column - star.column())
+                    .ok()
+                    .filter(|&away| {
+                        the_game.sector_map.sector_contents_at(away) == SectorContents::Empty
+                    });
+                match the_game.sector_map.sector_contents_at(sector) {
+                    SectorContents::Star if !stars.contains(&sector) => stars.push(sector),
+                    SectorContents::Klingon => buffet_klingon(the_game, sector, away),
+                    SectorContents::Enterprise => buffet_enterprise(the_game, away)?,
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+} /* End nova */
+
+/// A Klingon next to a nova is hit, and pushed away if it survives
+fn buffet_klingon(the_game: &mut TheGame, sector: Sector, away: Option<Sector>) {
+    let i = match the_game
+        .klingons
+        .iter()
+        .position(|klingon| klingon.sector() == sector)
+    {
+        Some(i) => i,
+        None => return,
+    };
+    the_game.klingons[i].drain_shields(NOVA_HIT);
+    let klingon = the_game.klingons[i];
+    the_game.emit(GameEvent::KlingonHit {
+        sector,
+        klingon: klingon.id(),
+        hit: NOVA_HIT,
+        shields_left: klingon.shields(),
+    });
+    if klingon.is_destroyed() {
+        the_game.emit(GameEvent::KlingonDestroyed {
+            sector,
+            klingon: klingon.id(),
+            weapon: Weapon::Nova,
+        });
+        the_game.klingons.remove(i);
+        the_game.sector_map[sector] = SectorContents::Empty.into();
+        the_game.quadrant_klingons -= 1;
+        the_game.total_klingons -= 1;
+    } else if let Some(to) = away {
+        the_game.klingons[i].move_to(to);
+        the_game.sector_map[sector] = SectorContents::Empty.into();
+        the_game.sector_map[to] = SectorContents::Klingon.into();
+        the_game.emit(GameEvent::KlingonDisplaced {
+            klingon: klingon.id(),
+            from: sector,
+            to,
+        });
+    }
+}
+
+/// The Enterprise takes the blast on its shields, and is pushed away if there is room
+fn buffet_enterprise(the_game: &mut TheGame, away: Option<Sector>) -> StResult<()> {
+    let through = the_game.shields.absorb(NOVA_HIT);
+    the_game.energy -= through;
+    the_game.emit(GameEvent::EnterpriseBuffeted {
+        hit: NOVA_HIT,
+        energy_left: the_game.energy,
+        shields_left: the_game.shields_left(),
+    });
+    the_game.hit_damage(through)?;
+    if let Some(to) = away {
+        let from = the_game.sector;
+        the_game.sector_map[from] = SectorContents::Empty.into();
+        the_game.sector_map[to] = SectorContents::Enterprise.into();
+        the_game.sector = to;
+        // Knocked out of any orbit, though the landing party is still on the planet
+        the_game.orbiting = false;
+        the_game.emit(GameEvent::EnterpriseDisplaced { sector: to });
+    }
+    Ok(())
+}
+
+/// Wipe out a quadrant. If the Enterprise is there, get it out if it can be done.
+fn supernova(the_game: &mut TheGame, quadrant: Quadrant) -> StResult<()> {
+    let contents = the_game.quadrant_map[quadrant];
+    the_game.total_klingons -= contents.klingons;
+    the_game.supernova_kills += contents.klingons;
+    the_game.total_starbases -= contents.starbases;
+    let mut remains = QuadrantContents::new(0, 0, 0, false);
+    remains.supernova = true;
+    remains.impassable_until = Some(the_game.current_stardate + IMPASSABLE_YEARS);
+    the_game.quadrant_map[quadrant] = remains;
+    // Nobody misses a supernova
+    the_game.record_quadrant(quadrant);
+    the_game.emit(GameEvent::Supernova {
+        quadrant,
+        klingons: contents.klingons,
+        starbases: contents.starbases,
+    });
+    if quadrant != the_game.quadrant {
+        return Ok(());
+    }
+    the_game.klingons.clear();
+    the_game.romulans.clear();
+    the_game.quadrant_klingons = 0;
+    the_game.quadrant_starbases = 0;
+    the_game.quadrant_stars = 0;
+
+    let refuges = ((quadrant.row() - 1)..=(quadrant.row() + 1))
+        .flat_map(|row| {
+            ((quadrant.column() - 1)..=(quadrant.column() + 1)).map(move |column| (row, column))
+        })
+        .filter_map(|(row, column)| the_game.quadrant_map.quadrant(row, column).ok())
+        .filter(|&refuge| refuge != quadrant && !impassable(the_game, refuge))
+        .collect::<Vec<_>>();
+    if refuges.is_empty() {
+        the_game.caught_in_supernova = true;
+        the_game.game_state = GameState::Lost;
+        return Ok(());
+    }
+    let refuge = refuges[(rnd(&mut the_game.rng) * refuges.len() as f64).floor() as usize];
+    let through = the_game.shields.absorb(SUPERNOVA_HIT);
+    the_game.energy -= through;
+    the_game.emit(GameEvent::ThrownClear {
+        quadrant: refuge,
+        hit: SUPERNOVA_HIT,
+        energy_left: the_game.energy,
+    });
+    the_game.hit_damage(through)?;
+    // Nothing is left to orbit, or to beam back from
+    the_game.orbiting = false;
+    if the_game.landing_party != LandingParty::Aboard {
+        the_game.landing_party = LandingParty::Aboard;
+        the_game.emit(GameEvent::PartyLost);
+    }
+    the_game.quadrant = refuge;
+    the_game.new_quadrant = true;
+    Ok(())
+} /* End supernova */
+
+/// Whether the remains of a supernova still block the way into a quadrant
+pub(crate) fn impassable(the_game: &TheGame, quadrant: Quadrant) -> bool {
+    matches!(the_game.quadrant_map[quadrant].impassable_until,
+        Some(until) if the_game.current_stardate < until)
+}
+
+/// Pick the stardate of the next supernova somewhere in the galaxy
+pub(crate) fn schedule_supernova(the_game: &mut TheGame) {
+    let interval = the_game.game_defs.supernova_interval;
+    the_game.next_supernova = if interval > 0 {
+        let years = 1 + (rnd(&mut the_game.rng) * (2 * interval) as f64).floor() as i32;
+        Some(the_game.current_stardate + years)
+    } else {
+        None
+    };
+}
+
+/// Set off the scheduled supernova if its stardate has come, in a quadrant away from the
+/// Enterprise that still has stars
+pub(crate) fn scheduled_supernova(the_game: &mut TheGame) -> StResult<()> {
+    match the_game.next_supernova {
+        Some(when) if the_game.current_stardate >= when => {}
+        _ => return Ok(()),
+    }
+    let candidates = the_game
+        .quadrant_map
+        .quadrants()
+        .filter(|&quadrant| {
+            let contents = the_game.quadrant_map[quadrant];
+            quadrant != the_game.quadrant && !contents.supernova && contents.stars > 0
+        })
+        .collect::<Vec<_>>();
+    if !candidates.is_empty() {
+        let quadrant =
+            candidates[(rnd(&mut the_game.rng) * candidates.len() as f64).floor() as usize];
+        supernova(the_game, quadrant)?;
+    }
+    schedule_supernova(the_game);
+    Ok(())
+} /* End scheduled_supernova */
+
+/// The Enterprise is headed into the remains of a supernova. The navigation computer drops it out
+/// of warp back where it started, unless the computer is down, when nothing stops it.
+pub(crate) fn supernova_in_the_way(the_game: &mut TheGame, to: Quadrant, start: Sector) {
+    if the_game.damage.is_damaged(COMPUTER.into(), false) {
+        the_game.quadrant = to;
+        the_game.caught_in_supernova = true;
+        the_game.game_state = GameState::Lost;
+        the_game.emit(GameEvent::EnteredSupernova { quadrant: to });
+    } else {
+        the_game.new_quadrant = false;
+        the_game.sector = start;
+        the_game.sector_map[start] = SectorContents::Enterprise.into();
+        // As if the Enterprise had stayed within the quadrant
+        the_game.saved_command = 2.into();
+        the_game.emit(GameEvent::WarpDiverted { quadrant: to });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+    use crate::the_game::{new_game, Action, Ending, Klingon, TheGameDefsBuilder};
+
+    #[test]
+    fn test_nova_chain_reaction() -> StResult<()> {
+        let mut the_game = new_game(2);
+        the_game.start()?;
+        the_game.damage.fix_damage();
+        let sector = |row, column| Sector::try_from((row, column));
+        let (enterprise, first, second) = (sector(3, 1)?, sector(3, 2)?, sector(4, 3)?);
+        let (near, doomed) = (sector(2, 2)?, sector(5, 4)?);
+
+        // Just the Enterprise, two stars and two Klingons
+        for sector in the_game.sector_map.sectors().collect::<Vec<_>>() {
+            the_game.sector_map[sector] = SectorContents::Empty.into();
+        }
+        the_game.sector_map[enterprise] = SectorContents::Enterprise.into();
+        the_game.sector = enterprise;
+        for star in [first, second] {
+            the_game.sector_map[star] = SectorContents::Star.into();
+        }
+        the_game.quadrant_stars = 2;
+        the_game.total_klingons += 2 - the_game.quadrant_klingons;
+        the_game.quadrant_klingons = 2;
+        the_game.romulans.clear();
+        the_game.klingons = vec![
+            Klingon::new(100, near, the_game.s9()),
+            Klingon::new(101, doomed, NOVA_HIT / 2.0),
+        ];
+        for klingon in the_game.klingons.clone() {
+            the_game.sector_map[klingon.sector()] = SectorContents::Klingon.into();
+        }
+        let total_klingons = the_game.total_klingons;
+        the_game.orbiting = true;
+
+        nova(&mut the_game, first)?;
+        let events = the_game.take_events();
+        assert!(events.contains(&GameEvent::Nova { sector: first }));
+        assert!(events.contains(&GameEvent::Nova { sector: second }));
+        assert_eq!(0, the_game.quadrant_stars);
+        assert!(events.contains(&GameEvent::KlingonDisplaced {
+            klingon: 100,
+            from: near,
+            to: sector(1, 2)?,
+        }));
+        assert!(events.contains(&GameEvent::KlingonDestroyed {
+            sector: doomed,
+            klingon: 101,
+            weapon: Weapon::Nova,
+        }));
+        assert_eq!(total_klingons - 1, the_game.total_klingons);
+        assert_eq!(sector(3, 0)?, the_game.sector);
+        assert!(!the_game.orbiting);
+        assert_eq!(
+            SectorContents::Enterprise,
+            the_game.sector_map.sector_contents_at(the_game.sector)
+        );
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, GameEvent::EnterpriseBuffeted { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_supernova_empties_the_quadrant() -> StResult<()> {
+        let mut the_game = new_game(23);
+        the_game.start()?;
+        the_game.damage.fix_damage();
+        let quadrant = the_game.quadrant;
+        let contents = the_game.quadrant_map[quadrant];
+        let total_klingons = the_game.total_klingons;
+        let total_starbases = the_game.total_starbases;
+        the_game.orbiting = true;
+        the_game.landing_party = LandingParty::Ashore;
+
+        supernova(&mut the_game, quadrant)?;
+        assert!(the_game.quadrant_map[quadrant].supernova);
+        assert_eq!(0, the_game.quadrant_map[quadrant].klingons);
+        assert_eq!(total_klingons - contents.klingons, the_game.total_klingons);
+        assert_eq!(
+            total_starbases - contents.starbases,
+            the_game.total_starbases
+        );
+        assert_ne!(quadrant, the_game.quadrant);
+        assert!(the_game.new_quadrant);
+        assert!(!the_game.orbiting);
+        assert_eq!(LandingParty::Aboard, the_game.landing_party);
+        assert!(the_game.take_events().contains(&GameEvent::PartyLost));
+        Ok(())
+    }
+
+    #[test]
+    fn test_scheduled_supernova() -> StResult<()> {
+        let mut the_game = new_game(1);
+        the_game.start()?;
+        the_game.damage.fix_damage();
+        let total_klingons = the_game.total_klingons;
+        the_game.next_supernova = Some(the_game.current_stardate);
+
+        scheduled_supernova(&mut the_game)?;
+        let (quadrant, klingons) = match the_game.take_events().as_slice() {
+            [GameEvent::Supernova {
+                quadrant, klingons, ..
+            }] => (*quadrant, *klingons),
+            events => panic!("expected a supernova, got {:?}", events),
+        };
+        assert_ne!(the_game.quadrant, quadrant);
+        assert!(impassable(&the_game, quadrant));
+        assert!(the_game.records[quadrant].supernova);
+        assert_eq!(total_klingons - klingons, the_game.total_klingons);
+        assert_eq!(klingons, the_game.supernova_kills);
+        assert!(the_game.next_supernova > Some(the_game.current_stardate));
+        Ok(())
+    }
+
+    #[test]
+    fn test_supernova_blocks_warp() -> StResult<()> {
+        let the_game_defs = TheGameDefsBuilder::default()
+            .seed(1)
+            .storm_probability(0.0)
+            .build()
+            .unwrap();
+        let mut the_game = TheGame::new(&the_game_defs);
+        the_game.start()?;
+        the_game.damage.fix_damage();
+        the_game.next_supernova = None;
+
+        // Nothing in the way, nobody to shoot back, and supernovas all around
+        for sector in the_game.sector_map.sectors().collect::<Vec<_>>() {
+            the_game.sector_map[sector] = SectorContents::Empty.into();
+        }
+        let start = the_game.sector;
+        the_game.sector_map[start] = SectorContents::Enterprise.into();
+        the_game.total_klingons -= the_game.quadrant_klingons;
+        the_game.quadrant_klingons = 0;
+        the_game.klingons.clear();
+        the_game.romulans.clear();
+        let quadrant = the_game.quadrant;
+        for other in the_game.quadrant_map.quadrants().collect::<Vec<_>>() {
+            if other != quadrant {
+                the_game.quadrant_map[other].supernova = true;
+                the_game.quadrant_map[other].impassable_until =
+                    Some(the_game.current_stardate + IMPASSABLE_YEARS);
+            }
+        }
+        let course = if quadrant.column() + 1 < the_game.quadrant_map.width() {
+            1.0
+        } else {
+            5.0
+        };
+
+        let events = the_game.apply(Action::Warp { course, warp: 1.0 })?;
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, GameEvent::WarpDiverted { .. })));
+        assert_eq!(quadrant, the_game.quadrant);
+        assert_eq!(start, the_game.sector);
+
+        // Without the computer, nothing holds the Enterprise back
+        the_game.damage.add_damage(COMPUTER.into(), 5);
+        let events = the_game.apply(Action::Warp { course, warp: 1.0 })?;
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, GameEvent::EnteredSupernova { .. })));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            GameEvent::GameOver {
+                ending: Ending::CaughtInSupernova { .. },
+                ..
+            }
+        )));
+        Ok(())
+    }
+}
@@ -1,31 +1,26 @@
-//!
+//! # startrust::the_game::path
 
 use std::f64::consts::FRAC_PI_4;
 
-use termcolor::WriteColor;
-
 use crate::the_game::commands::Command;
-use crate::the_game::quadrant::{Quadrant, QuadrantContents};
-use crate::the_game::{Sector, SectorContents};
+use crate::the_game::hazards::{black_hole_pull, swallow_enterprise};
+use crate::the_game::nova::{impassable, nova, supernova_in_the_way};
+use crate::the_game::romulan::provoke_romulans;
+use crate::the_game::{GameEvent, SectorContents, Weapon};
 use crate::util::{gt, lt};
 use crate::{StResult, StarTrustError, TheGame};
 
 /// Do the path for warp orself torpedo
-pub fn do_path<W: WriteColor>(
-    the_game: &mut TheGame,
-    sout: &mut W,
-    command: Command,
-    n: f64,
-) -> StResult<()> {
-    let mut y1 = the_game.s1 as f64 + 0.5;
-    let mut x1 = the_game.s2 as f64 + 0.5;
-    let mut y3 = (the_game.course - 1.0) as f64 * FRAC_PI_4; // `FRAC_PI_4` _was_ `0.785398`
+pub fn do_path(the_game: &mut TheGame, command: Command, n: f64) -> StResult<()> {
+    let start = the_game.sector;
+    let mut y1 = start.row() as f64 + 0.5;
+    let mut x1 = start.column() as f64 + 0.5;
+    let mut y3 = (the_game.course - 1.0) * FRAC_PI_4; // `FRAC_PI_4` _was_ `0.785398`
     let x3 = y3.cos();
     y3 = -(y3.sin());
     let mut in_quadrant = true;
     let mut short_move = command == Command::WarpEngines; // Command #1
-    let mut y7 = 0;
-    let mut x7 = 0;
+    let mut target_sector = the_game.sector_map.sector(0, 0)?;
     let mut y2 = the_game.game_defs.y2;
     let mut x2 = the_game.game_defs.x2;
     for _ in 0..(n as usize) {
@@ -33,61 +28,62 @@ pub fn do_path<W: WriteColor>(
         x1 += x3;
         y2 = y1.floor();
         x2 = x1.floor();
-        y7 = y2 as i32;
-        x7 = x2 as i32;
-        if (x7 < 0) || (x7 > 7) || (y7 < 0) || (y7 > 7) {
+        if !the_game.sector_map.contains(y2 as i32, x2 as i32) {
             in_quadrant = false;
             short_move = false;
             break;
         }
+        target_sector = the_game.sector_map.sector(y2 as i32, x2 as i32)?;
         if command == Command::PhotonTorpedos
         // Command #5
         {
             // Show torpedo track
-            write!(sout, "{} - {}  ", y7 + 1, x7 + 1)?;
-            sout.flush()?;
+            the_game.emit(GameEvent::TorpedoTrack {
+                sector: target_sector,
+            });
         }
-        if the_game
-            .sector_map
-            .sector_contents_at_coords(y7 as i32, x7 as i32)
-            != SectorContents::Empty
+        if the_game.sector_map.sector_contents_at(target_sector) != SectorContents::Empty
         // Content type 1
         {
             // Object blocking move or hit by torpedo
             short_move = false;
             break;
         }
+        if command == Command::WarpEngines {
+            if let Some(black_hole) = black_hole_pull(the_game, target_sector) {
+                swallow_enterprise(the_game, black_hole);
+                return Ok(());
+            }
+        }
     }
 
     if in_quadrant {
         // Still in quadrant -- short move, block, or torpedo hit
         the_game.new_quadrant = false;
-        writeln!(sout)?;
+        let target = the_game.sector_map.sector_contents_at(target_sector);
+        let mut target_klingon = None;
+        // Whether a torpedoed Romulan had it coming
+        let mut romulan_provoked = false;
         if !short_move {
-            if command == Command::WarpEngines
-            // Command #1
-            {
-                write!(sout, "BLOCKED BY ")?;
-                sout.flush()?;
-            }
-            match the_game
-                .sector_map
-                .sector_contents_at_coords(y7 as i32, x7 as i32)
-            {
+            match target {
                 SectorContents::Klingon => {
                     // case 3 :
                     // Klingon
-                    write!(sout, "KLINGON")?;
-                    sout.flush()?;
                     if command == Command::PhotonTorpedos
                     // Command #5
                     {
                         // Torpedo
-                        for i in 0..8 {
-                            if (y7 == the_game.k1[i] as i32) && (x7 == the_game.k2[i] as i32) {
-                                the_game.k3[i] = 0.0;
-                            }
-                        }
+                        let i = the_game
+                            .klingons
+                            .iter()
+                            .position(|klingon| klingon.sector() == target_sector)
+                            .ok_or_else(|| {
+                                StarTrustError::GameStateError(format!(
+                                    "No Klingon at sector {:?}",
+                                    target_sector
+                                ))
+                            })?;
+                        target_klingon = Some(the_game.klingons.remove(i).id());
                         the_game.quadrant_klingons -= 1;
                         the_game.total_klingons -= 1;
                     }
@@ -95,8 +91,6 @@ pub fn do_path<W: WriteColor>(
                 SectorContents::Starbase => {
                     // case 4 :
                     // Starbase
-                    write!(sout, "STARBASE")?;
-                    sout.flush()?;
                     if command == Command::PhotonTorpedos
                     // Command #5
                     {
@@ -104,18 +98,37 @@ pub fn do_path<W: WriteColor>(
                         the_game.quadrant_starbases = 2;
                     }
                 }
-                SectorContents::Star => {
-                    // case 5 :
-                    // Star
-                    write!(sout, "STAR")?;
-                    sout.flush()?;
+                SectorContents::Romulan => {
                     if command == Command::PhotonTorpedos
                     // Command #5
                     {
                         // Torpedo
-                        the_game.quadrant_stars -= 1;
+                        the_game
+                            .romulans
+                            .retain(|romulan| romulan.sector() != target_sector);
+                        romulan_provoked = the_game.romulans_provoked;
+                        if !romulan_provoked {
+                            the_game.unprovoked_romulan_kills += 1;
+                        }
                     }
                 }
+                SectorContents::Star => {
+                    // case 5 :
+                    // Star (a torpedo sets it off below)
+                }
+                SectorContents::Planet => {
+                    // Torpedoes burn up in the atmosphere, and the planet stays put
+                }
+                SectorContents::BlackHole => {
+                    if command == Command::WarpEngines
+                    // Command #1
+                    {
+                        // Straight in
+                        swallow_enterprise(the_game, target_sector);
+                        return Ok(());
+                    }
+                }
+                SectorContents::Nebula => {}
                 _ => {
                     return Err(StarTrustError::GameStateError(
                         "Ship blocked by unknown object".to_string(),
@@ -126,43 +139,93 @@ pub fn do_path<W: WriteColor>(
             // Command #1
             {
                 // Enterprise move
-                writeln!(sout, " AT SECTOR {} - {}", y7 + 1, x7 + 1)?;
+                the_game.emit(GameEvent::Blocked {
+                    by: target,
+                    sector: target_sector,
+                });
+                if target == SectorContents::Romulan {
+                    // Rammed
+                    provoke_romulans(the_game);
+                }
                 y2 = (y1 - y3).floor();
                 x2 = (x1 - x3).floor();
-                y7 = y2 as i32;
-                x7 = x2 as i32;
             }
         }
         if command == Command::WarpEngines
         // Command #1
         {
-            the_game.s1 = y2 as i32;
-            the_game.s2 = x2 as i32;
-            let the_sector = the_game.current_sector();
+            the_game.sector = the_game.sector_map.sector(y2 as i32, x2 as i32)?;
+            let the_sector = the_game.sector;
             the_game.sector_map[the_sector] = 2;
+            if short_move {
+                the_game.emit(GameEvent::MovedWithinQuadrant { sector: the_sector });
+            }
             // Flag to show we stayed within quadrant
             the_game.saved_command = 2.into();
         } else if command == Command::PhotonTorpedos
         // Command #5
         {
             // Torpedo
-            write!(sout, " DESTROYED!")?;
-            sout.flush()?;
+            if target == SectorContents::Star {
+                nova(the_game, target_sector)?;
+                if the_game.new_quadrant || the_game.game_state.is_done() {
+                    // Supernova, and there is nothing left here to account for
+                    return Ok(());
+                }
+            } else {
+                the_game.emit(match (target, target_klingon) {
+                    (_, Some(klingon)) => GameEvent::KlingonDestroyed {
+                        sector: target_sector,
+                        klingon,
+                        weapon: Weapon::PhotonTorpedo,
+                    },
+                    (SectorContents::Romulan, _) => GameEvent::RomulanDestroyed {
+                        sector: target_sector,
+                        provoked: romulan_provoked,
+                    },
+                    (SectorContents::Planet, _) => GameEvent::PlanetHit {
+                        sector: target_sector,
+                    },
+                    (SectorContents::BlackHole, _) => GameEvent::TorpedoSwallowed {
+                        sector: target_sector,
+                    },
+                    (SectorContents::Nebula, _) => GameEvent::TorpedoLostInNebula {
+                        sector: target_sector,
+                    },
+                    (SectorContents::Starbase, _) => GameEvent::StarbaseDestroyed {
+                        sector: target_sector,
+                    },
+                    _ => {
+                        return Err(StarTrustError::GameStateError(format!(
+                            "Torpedo hit unknown object at sector {:?}",
+                            target_sector
+                        )))
+                    }
+                });
+            }
             if the_game.quadrant_starbases == 2 {
                 the_game.quadrant_starbases = 0;
-                write!(sout, " . . . GOOD WORK!")?;
-                sout.flush()?;
             }
-            writeln!(sout)?;
-            let old_sector = Sector::new(y7 as i32, x7 as i32);
-            the_game.sector_map[old_sector] = SectorContents::Empty.into(); // Clear old sector (set it to 1)
-            let current_quadrant = Quadrant::new(the_game.q1, the_game.q2);
-            the_game.quadrant_map[current_quadrant] = QuadrantContents::new(
-                the_game.quadrant_klingons,
-                the_game.quadrant_starbases,
-                the_game.quadrant_stars,
-                false,
-            );
+            if !matches!(
+                target,
+                SectorContents::Star
+                    | SectorContents::Planet
+                    | SectorContents::BlackHole
+                    | SectorContents::Nebula
+            ) {
+                the_game.sector_map[target_sector] = SectorContents::Empty.into();
+                // Clear old sector (set it to 1)
+            }
+            let current_quadrant = the_game.quadrant;
+            let contents = &mut the_game.quadrant_map[current_quadrant];
+            contents.klingons = the_game.quadrant_klingons;
+            contents.starbases = the_game.quadrant_starbases;
+            contents.stars = the_game.quadrant_stars;
+            contents.romulans = the_game.romulans.len() as i32;
+            contents.show();
+            if target == SectorContents::Romulan {
+                provoke_romulans(the_game);
+            }
         }
     } else {
         // Out of quadrant -- move to new quadrant or torpedo miss
@@ -171,24 +234,33 @@ pub fn do_path<W: WriteColor>(
         {
             // Move
             the_game.new_quadrant = true;
-            the_game.q1 = (the_game.q1 as f64
+            let last_row = (the_game.quadrant_map.height() - 1) as f64;
+            let last_column = (the_game.quadrant_map.width() - 1) as f64;
+            let mut row = (the_game.quadrant.row() as f64
                 + the_game.warp * y3
-                + (the_game.s1 as f64 + 0.5) / 8.0)
+                + (start.row() as f64 + 0.5) / the_game.sector_map.height() as f64)
                 .floor() as i32;
-            the_game.q2 = (the_game.q2 as f64
+            let mut column = (the_game.quadrant.column() as f64
                 + the_game.warp * x3
-                + (the_game.s2 as f64 + 0.5) / 8.0)
+                + (start.column() as f64 + 0.5) / the_game.sector_map.width() as f64)
                 .floor() as i32;
-            the_game.q1 = (the_game.q1 as i32 - lt(the_game.q1 as f64, 0.0)
-                + gt(the_game.q1 as f64, 7.0)) as i32;
-            the_game.q2 = (the_game.q2 as i32 - lt(the_game.q2 as f64, 0.0)
-                + gt(the_game.q2 as f64, 7.0)) as i32;
-            the_game.normalize_current_quadrant();
+            row = row - lt(row as f64, 0.0) + gt(row as f64, last_row);
+            column = column - lt(column as f64, 0.0) + gt(column as f64, last_column);
+            // The Enterprise stops at the edge of the galaxy
+            let to = the_game.quadrant_map.quadrant(
+                row.clamp(0, the_game.quadrant_map.height() - 1),
+                column.clamp(0, the_game.quadrant_map.width() - 1),
+            )?;
+            if to != the_game.quadrant && impassable(the_game, to) {
+                supernova_in_the_way(the_game, to, start);
+            } else {
+                the_game.quadrant = to;
+            }
         } else if command == Command::PhotonTorpedos
         // Command #5
         {
             // Torpedo
-            writeln!(sout, "MISSED!")?;
+            the_game.emit(GameEvent::TorpedoMissed);
         }
     }
     Ok(())
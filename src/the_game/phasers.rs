@@ -1,101 +1,262 @@
-use std::io::BufRead;
+//! # startrust::the_game::phasers
 
 use log::debug;
-use termcolor::WriteColor;
 
-use crate::interaction::{getinp, InputMode, InputValue};
 use crate::the_game::damage::Component;
-use crate::the_game::{GameState, Sector};
-use crate::{StResult, TheGame};
+use crate::the_game::hazards::phaser_range;
+use crate::the_game::{GameEvent, GameState, Sector, Weapon};
+use crate::{StResult, StarTrustError, TheGame};
 
 const PHASERS: Component = Component::Phasers; // Component # 3
 
-/// Fire phasers
-pub fn phasers<R: BufRead, W: WriteColor>(
-    the_game: &mut TheGame,
-    sin: &mut R,
-    sout: &mut W,
-) -> StResult<GameState> {
-    let mut gamecomp = GameState::InProgress;
+/// How the energy fired from the phasers is shared out among the Klingons
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum PhaserTargeting {
+    /// The same to every Klingon, as the phasers always have
+    #[default]
+    Even,
+    /// More to the nearer Klingons, so that they go down first
+    Weighted,
+    /// In proportion to these amounts, by Klingon id. Klingons that aren't named get nothing.
+    Manual(Vec<(usize, f64)>),
+}
+
+impl PhaserTargeting {
+    /// Each Klingon's share of the energy, in the order of `the_game.klingons`
+    fn shares(&self, the_game: &TheGame) -> Vec<f64> {
+        let weights: Vec<f64> = the_game
+            .klingons
+            .iter()
+            .map(|klingon| match self {
+                PhaserTargeting::Even => 1.0,
+                PhaserTargeting::Weighted => {
+                    1.0 / firing_distance(klingon.sector(), the_game.sector)
+                }
+                PhaserTargeting::Manual(amounts) => amounts
+                    .iter()
+                    .filter(|(id, _)| *id == klingon.id())
+                    .map(|(_, amount)| amount)
+                    .sum(),
+            })
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return vec![0.0; weights.len()];
+        }
+        weights.iter().map(|weight| weight / total).collect()
+    }
+}
 
+/// Explain why the phasers can't fire this much energy this way, if they can't
+pub fn phasers_rejection(
+    the_game: &TheGame,
+    x: f64,
+    targeting: &PhaserTargeting,
+) -> Vec<GameEvent> {
+    let mut events = Vec::new();
     if the_game.damage.is_damaged(PHASERS.into(), false) {
         // Phasers inoperative
-        the_game.damage.show_damage(sout, PHASERS)?;
-    } else {
-        let mut x;
-
-        loop {
-            write!(sout, "PHASERS READY: ENERGY UNITS TO FIRE? ")?;
-            sout.flush()?;
-            let gb = getinp(sin, sout, 15, InputMode::Mode2)?;
-            writeln!(sout)?;
-            if let InputValue::InputString(ibuff) = gb {
-                x = ibuff.parse()?;
-            } else {
-                x = 0.0;
-                break;
-            }
-            if x <= the_game.energy {
-                break;
-            }
-            writeln!(sout, "ONLY GOT {:03}", the_game.energy)?; // The printf format was "%.3f"
+        the_game.damage.show_damage(&mut events, PHASERS);
+    } else if x < 0.0 || !manual_targeting_okay(the_game, x, targeting) {
+        events.push(GameEvent::InvalidOrder);
+    } else if x > the_game.energy {
+        events.push(GameEvent::NotEnoughEnergy {
+            energy: the_game.energy,
+        });
+    }
+    events
+}
+
+/// Manual targeting has to name Klingons that are here, and give them something to share
+fn manual_targeting_okay(the_game: &TheGame, x: f64, targeting: &PhaserTargeting) -> bool {
+    let amounts = match targeting {
+        PhaserTargeting::Manual(amounts) => amounts,
+        _ => return true,
+    };
+    amounts.iter().all(|&(id, amount)| {
+        amount >= 0.0 && the_game.klingons.iter().any(|klingon| klingon.id() == id)
+    }) && (x == 0.0 || amounts.iter().any(|&(_, amount)| amount > 0.0))
+}
+
+/// Fire phasers
+pub fn phasers(the_game: &mut TheGame, x: f64, targeting: &PhaserTargeting) -> StResult<GameState> {
+    let mut gamecomp = GameState::InProgress;
+
+    let shares = targeting.shares(the_game);
+    if shares.iter().any(|share| !share.is_finite()) {
+        return Err(StarTrustError::GameStateError(format!(
+            "phaser shares {:?} can't all be fired",
+            shares
+        )));
+    }
+    the_game.energy -= x;
+    for (i, share) in shares.into_iter().enumerate() {
+        if share <= 0.0 {
+            // Not aimed at this one
+            continue;
         }
-        the_game.energy -= x;
-        let y3 = the_game.quadrant_klingons as f64;
-        for i in 0..8 {
-            if the_game.k3[i] > 0.0 {
-                let f = fnd(the_game.k1[i], the_game.k2[i], the_game.s1, the_game.s2);
-                debug!("About to fire phasers: x = {}, y3 = {}, f = {}", x, y3, f);
-                let h = x / (y3 * f.powf(0.4));
-                the_game.k3[i] -= h;
-                let n = the_game.k3[i];
-                the_game.show_hit(sout, i, "KLINGON AT", n, h)?;
-                if the_game.k3[i] <= 0.0 {
-                    writeln!(sout, "**KLINGON DESTROYED**")?;
-                    the_game.quadrant_klingons -= 1;
-                    the_game.total_klingons -= 1;
-                    let sector = Sector::new(the_game.k1[i], the_game.k2[i]);
-                    the_game.sector_map[sector] = 1;
-                    let quadrant = the_game.current_quadrant();
-                    the_game.quadrant_map[quadrant].decrement_klingons();
-                }
-            }
+        let sector = the_game.klingons[i].sector();
+        let f = firing_distance(sector, the_game.sector);
+        if f > phaser_range(the_game) {
+            the_game.emit(GameEvent::PhaserOutOfRange {
+                sector,
+                klingon: the_game.klingons[i].id(),
+            });
+            continue;
+        }
+        debug!(
+            "About to fire phasers: x = {}, share = {}, f = {}",
+            x, share, f
+        );
+        let h = x * share / f.powf(0.4);
+        if !h.is_finite() {
+            return Err(StarTrustError::GameStateError(format!(
+                "phaser hit of {} on Klingon at {:?}",
+                h, sector
+            )));
         }
+        the_game.klingons[i].drain_shields(h);
+        let klingon = the_game.klingons[i];
+        the_game.emit(GameEvent::KlingonHit {
+            sector,
+            klingon: klingon.id(),
+            hit: h,
+            shields_left: klingon.shields(),
+        });
+        if klingon.is_destroyed() {
+            the_game.emit(GameEvent::KlingonDestroyed {
+                sector,
+                klingon: klingon.id(),
+                weapon: Weapon::Phasers,
+            });
+            the_game.quadrant_klingons -= 1;
+            the_game.total_klingons -= 1;
+            the_game.sector_map[sector] = 1;
+            let quadrant = the_game.quadrant;
+            the_game.quadrant_map[quadrant].decrement_klingons();
+        }
+    }
+    the_game.klingons.retain(|klingon| !klingon.is_destroyed());
 
-        if x > 0.0 {
-            if the_game.energy <= 0.0 {
-                /* Ran out of energy */
-                gamecomp = (-1).into();
-            }
-            the_game.check_for_hits(sout)?;
-            if the_game.energy <= 0.0 {
-                /* Ran out of energy */
-                gamecomp = (-1).into();
-            }
-            if the_game.total_klingons < 1 {
-                /* All Klingons destroyed! */
-                gamecomp = 1.into();
-            }
-            if !gamecomp.is_done() {
-                the_game.check_condition()
-            };
+    if x > 0.0 {
+        if the_game.energy <= 0.0 {
+            /* Ran out of energy */
+            gamecomp = (-1).into();
+        }
+        the_game.check_for_hits()?;
+        if the_game.energy <= 0.0 {
+            /* Ran out of energy */
+            gamecomp = (-1).into();
+        }
+        if the_game.total_klingons < 1 {
+            /* All Klingons destroyed! */
+            gamecomp = 1.into();
         }
+        if !gamecomp.is_done() {
+            the_game.check_condition()
+        };
     }
     Ok(gamecomp)
 } /* End phasers */
 
-/// Determine damage hit amount (distance-dependent)
-pub fn fnd(k1_i: i32, k2_i: i32, s1: i32, s2: i32) -> f64 {
-    let k1_i = k1_i as f64; // k1[i]
-    let k2_i = k2_i as f64; // k2[i]
-    let s1 = s1 as f64;
-    let s2 = s2 as f64;
+/// How far a shot has to travel, never less than one sector even if the records somehow put the
+/// two ships in the same one
+pub(crate) fn firing_distance(klingon: Sector, enterprise: Sector) -> f64 {
+    fnd(klingon, enterprise).max(1.0)
+}
 
-    let dx = (k1_i - s1).abs();
-    let dy = (k2_i - s2).abs();
+/// Determine damage hit amount (distance-dependent)
+pub fn fnd(klingon: Sector, enterprise: Sector) -> f64 {
+    let dx = (klingon.row() - enterprise.row()).abs() as f64;
+    let dy = (klingon.column() - enterprise.column()).abs() as f64;
 
     let dx2 = dx.powi(2);
     let dy2 = dy.powi(2);
 
     (dx2 + dy2).sqrt()
 } /* End fnd */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{new_game, Action, Klingon};
+
+    #[test]
+    fn test_phaser_targeting() -> StResult<()> {
+        let hits = |targeting: PhaserTargeting| -> StResult<Vec<(usize, f64)>> {
+            let mut the_game = new_game(23);
+            the_game.start()?;
+            assert!(the_game.klingons.len() > 2);
+            Ok(the_game
+                .apply(Action::Phasers {
+                    energy: 300.0,
+                    targeting,
+                })?
+                .iter()
+                .filter_map(|event| match event {
+                    GameEvent::KlingonHit { klingon, hit, .. } => Some((*klingon, *hit)),
+                    _ => None,
+                })
+                .collect())
+        };
+        let mut the_game = new_game(23);
+        the_game.start()?;
+        let distance = |id: usize| {
+            let klingon = the_game.klingons.iter().find(|k| k.id() == id).unwrap();
+            fnd(klingon.sector(), the_game.sector)
+        };
+
+        // Weighting by distance gives the nearer Klingons more than an even split does
+        let even = hits(PhaserTargeting::Even)?;
+        let weighted = hits(PhaserTargeting::Weighted)?;
+        assert_eq!(even.len(), weighted.len());
+        let nearest = even
+            .iter()
+            .map(|&(id, _)| id)
+            .min_by(|&a, &b| distance(a).partial_cmp(&distance(b)).unwrap())
+            .unwrap();
+        let hit_on = |hits: &[(usize, f64)], id| hits.iter().find(|h| h.0 == id).unwrap().1;
+        assert!(hit_on(&weighted, nearest) > hit_on(&even, nearest));
+
+        // Manual targeting only hits the Klingons it names, with what they were given
+        let manual = hits(PhaserTargeting::Manual(vec![(nearest, 300.0)]))?;
+        assert_eq!(1, manual.len());
+        assert_eq!(nearest, manual[0].0);
+        assert!((manual[0].1 - 300.0 / distance(nearest).powf(0.4)).abs() < 1e-9);
+
+        // A Klingon that isn't here can't be targeted
+        assert_eq!(
+            vec![GameEvent::InvalidOrder],
+            the_game.apply(Action::Phasers {
+                energy: 100.0,
+                targeting: PhaserTargeting::Manual(vec![(the_game.next_klingon_id, 100.0)]),
+            })?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_phasers_at_point_blank_range() -> StResult<()> {
+        let mut the_game = new_game(23);
+        the_game.start()?;
+        the_game.damage.fix_damage();
+        // A Klingon recorded in the Enterprise's own sector mustn't turn the hit into NaN
+        let klingon = the_game.klingons[0];
+        the_game.klingons[0] = Klingon::new(klingon.id(), the_game.sector, klingon.shields());
+        let events = the_game.apply(Action::Phasers {
+            energy: 300.0,
+            targeting: PhaserTargeting::Weighted,
+        })?;
+        assert!(events.iter().all(|event| match event {
+            GameEvent::KlingonHit { hit, .. } => hit.is_finite(),
+            _ => true,
+        }));
+        assert!(the_game.energy.is_finite());
+
+        let mut buffer = Vec::new();
+        the_game.save(&mut buffer)?;
+        let restored_game = TheGame::load(buffer.as_slice())?;
+        assert_eq!(the_game.energy, restored_game.energy);
+        Ok(())
+    }
+}
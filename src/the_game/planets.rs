@@ -0,0 +1,302 @@
+//! # startrust::the_game::planets
+//!
+//! Planets, and the dilithium crystals that some of them hold. The Enterprise has to be in orbit
+//! to send a landing party down, by transporter (with the shields lowered) or by shuttle craft.
+//! Crystals the party mines can be fed to the engines for an emergency boost of energy when there
+//! is no starbase to be had, though they don't always take.
+
+use serde::{Deserialize, Serialize};
+use strum_macros::AsRefStr;
+
+use crate::the_game::damage::Component;
+use crate::the_game::{GameEvent, SectorContents};
+use crate::util::rnd;
+use crate::{StResult, TheGame};
+
+const WARP: Component = Component::WarpEngines; // Component #0
+
+/// The chance that a planet has dilithium crystals to mine
+const DILITHIUM_PROBABILITY: f64 = 0.4;
+/// The chance that a load of crystals fractures instead of boosting the engines
+const CRYSTAL_FAILURE_PROBABILITY: f64 = 0.2;
+
+/// The kind of planet, which the landing party finds out about from orbit
+#[derive(AsRefStr, Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum PlanetClass {
+    #[strum(serialize = "M")]
+    M,
+    #[strum(serialize = "N")]
+    N,
+    #[strum(serialize = "O")]
+    O,
+}
+
+const PLANET_CLASSES: [PlanetClass; 3] = [PlanetClass::M, PlanetClass::N, PlanetClass::O];
+
+/// The planet in a quadrant (there is never more than one)
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Planet {
+    class: PlanetClass,
+    /// Whether there are crystals left to mine
+    pub(crate) crystals: bool,
+}
+
+impl Planet {
+    pub fn new(class: PlanetClass, crystals: bool) -> Self {
+        Self { class, crystals }
+    }
+
+    pub fn class(&self) -> PlanetClass {
+        self.class
+    }
+}
+
+/// How a landing party gets to a planet and back
+#[derive(AsRefStr, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LandingCraft {
+    #[strum(serialize = "TRANSPORTER")]
+    Transporter,
+    #[strum(serialize = "SHUTTLE CRAFT")]
+    Shuttle,
+}
+
+/// Where the landing party is
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum LandingParty {
+    #[default]
+    Aboard,
+    Ashore,
+    /// On the planet, with crystals to bring back
+    AshoreWithCrystals,
+}
+
+/// What can be done about a planet
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PlanetOrder {
+    /// Go into standard orbit around a planet in a neighbouring sector
+    Orbit,
+    /// Send the landing party down, or bring it back up if it is already ashore
+    Transport(LandingCraft),
+    /// Have the landing party dig for dilithium
+    Mine,
+    /// Feed a load of crystals to the engines
+    UseCrystals,
+}
+
+/// Scatter planets across the galaxy as it is set up
+pub(crate) fn place_planets(the_game: &mut TheGame) {
+    let frequency = the_game.game_defs.planet_frequency;
+    for quadrant in the_game.quadrant_map.quadrants().collect::<Vec<_>>() {
+        if rnd(&mut the_game.rng) < frequency {
+            let class = PLANET_CLASSES
+                [(rnd(&mut the_game.rng) * PLANET_CLASSES.len() as f64).floor() as usize];
+            let crystals = rnd(&mut the_game.rng) < DILITHIUM_PROBABILITY;
+            the_game.quadrant_map[quadrant].planet = Some(Planet::new(class, crystals));
+        }
+    }
+}
+
+/// Whether the planet in this quadrant is in a sector next to the Enterprise
+fn planet_nearby(the_game: &TheGame) -> bool {
+    let row = the_game.sector.row();
+    let column = the_game.sector.column();
+    ((row - 1)..=(row + 1)).any(|i| {
+        ((column - 1)..=(column + 1)).any(|j| {
+            the_game.sector_map.sector(i, j).is_ok_and(|sector| {
+                the_game.sector_map.sector_contents_at(sector) == SectorContents::Planet
+            })
+        })
+    })
+}
+
+/// Explain why the order can't be carried out, if it can't
+pub fn planet_rejection(the_game: &TheGame, order: PlanetOrder) -> Vec<GameEvent> {
+    let mut events = Vec::new();
+    match order {
+        PlanetOrder::Orbit => {
+            if the_game.orbiting {
+                events.push(GameEvent::InvalidOrder);
+            } else if !planet_nearby(the_game) {
+                events.push(GameEvent::NoPlanetNearby);
+            }
+        }
+        PlanetOrder::Transport(craft) => {
+            if !the_game.orbiting {
+                events.push(GameEvent::NotInOrbit);
+            } else if craft == LandingCraft::Transporter && the_game.shields.is_up() {
+                events.push(GameEvent::TransporterBlockedByShields);
+            }
+        }
+        PlanetOrder::Mine => {
+            if the_game.landing_party == LandingParty::Aboard {
+                events.push(GameEvent::PartyNotAshore);
+            } else if the_game.landing_party == LandingParty::AshoreWithCrystals {
+                // They can't carry any more
+                events.push(GameEvent::InvalidOrder);
+            }
+        }
+        PlanetOrder::UseCrystals => {
+            if the_game.crystals < 1 {
+                events.push(GameEvent::NoCrystals);
+            }
+        }
+    }
+    events
+}
+
+/// Carry out an order about a planet, returning whether it took a turn
+pub fn planet(the_game: &mut TheGame, order: PlanetOrder) -> StResult<bool> {
+    let quadrant = the_game.quadrant;
+    match order {
+        PlanetOrder::Orbit => {
+            the_game.orbiting = true;
+            if let Some(planet) = the_game.quadrant_map[quadrant].planet {
+                the_game.emit(GameEvent::EnteredOrbit {
+                    class: planet.class(),
+                });
+            }
+            Ok(true)
+        }
+        PlanetOrder::Transport(by) => {
+            if the_game.landing_party == LandingParty::Aboard {
+                the_game.landing_party = LandingParty::Ashore;
+                the_game.emit(GameEvent::PartyLanded { by });
+            } else {
+                let crystals = the_game.landing_party == LandingParty::AshoreWithCrystals;
+                if crystals {
+                    the_game.crystals += 1;
+                }
+                the_game.landing_party = LandingParty::Aboard;
+                the_game.emit(GameEvent::PartyReturned { by, crystals });
+            }
+            // Beaming is instant, but the shuttle takes its time
+            Ok(by == LandingCraft::Shuttle)
+        }
+        PlanetOrder::Mine => {
+            let planet = &mut the_game.quadrant_map[quadrant].planet;
+            match planet {
+                Some(planet) if planet.crystals => {
+                    planet.crystals = false;
+                    the_game.landing_party = LandingParty::AshoreWithCrystals;
+                    the_game.emit(GameEvent::CrystalsMined);
+                }
+                _ => the_game.emit(GameEvent::NoDilithiumFound),
+            }
+            Ok(true)
+        }
+        PlanetOrder::UseCrystals => {
+            the_game.crystals -= 1;
+            if rnd(&mut the_game.rng) < CRYSTAL_FAILURE_PROBABILITY {
+                the_game.damage.add_damage(
+                    WARP.into(),
+                    2 + (rnd(&mut the_game.rng) * 3.0).floor() as i32,
+                );
+                the_game.emit(GameEvent::CrystalsFailed {
+                    repair_time: the_game.damage.get_damage(WARP),
+                });
+            } else {
+                the_game.energy += the_game.game_defs.initial_energy;
+                the_game.emit(GameEvent::CrystalsUsed {
+                    energy: the_game.energy,
+                });
+            }
+            Ok(false)
+        }
+    }
+} /* End planet */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{new_game, Action};
+
+    #[test]
+    fn test_planet_mining() -> StResult<()> {
+        let mut the_game = new_game(5);
+        the_game.start()?;
+        the_game.damage.fix_damage();
+        let quadrant = the_game.quadrant;
+        assert_eq!(
+            vec![GameEvent::NoPlanetNearby],
+            the_game.apply(Action::Planet(PlanetOrder::Orbit))?
+        );
+
+        // A planet with crystals next to the Enterprise
+        let row = the_game.sector.row();
+        let column = the_game.sector.column();
+        let sector = ((row - 1)..=(row + 1))
+            .flat_map(|i| ((column - 1)..=(column + 1)).map(move |j| (i, j)))
+            .filter_map(|(i, j)| the_game.sector_map.sector(i, j).ok())
+            .find(|&sector| the_game.sector_map.sector_contents_at(sector) == SectorContents::Empty)
+            .expect("the Enterprise is boxed in");
+        the_game.sector_map[sector] = SectorContents::Planet.into();
+        the_game.quadrant_map[quadrant].planet = Some(Planet::new(PlanetClass::M, true));
+
+        let orbit = Action::Planet(PlanetOrder::Orbit);
+        let transporter = Action::Planet(PlanetOrder::Transport(LandingCraft::Transporter));
+        let mine = Action::Planet(PlanetOrder::Mine);
+        let use_crystals = Action::Planet(PlanetOrder::UseCrystals);
+        assert_eq!(vec![GameEvent::NotInOrbit], the_game.validate(&transporter));
+        assert!(the_game.apply(orbit)?.contains(&GameEvent::EnteredOrbit {
+            class: PlanetClass::M
+        }));
+        assert_eq!(vec![GameEvent::PartyNotAshore], the_game.validate(&mine));
+        assert_eq!(
+            vec![GameEvent::TransporterBlockedByShields],
+            the_game.validate(&transporter)
+        );
+        the_game.apply(Action::Shields {
+            up: false,
+            transfer: 0.0,
+        })?;
+        assert!(the_game
+            .apply(transporter.clone())?
+            .contains(&GameEvent::PartyLanded {
+                by: LandingCraft::Transporter
+            }));
+
+        // Nobody leaves until the party is back
+        assert_eq!(
+            vec![GameEvent::PartyAshore],
+            the_game.validate(&Action::Warp {
+                course: 1.0,
+                warp: 1.0
+            })
+        );
+        assert!(the_game
+            .apply(mine.clone())?
+            .contains(&GameEvent::CrystalsMined));
+        assert_eq!(0, the_game.crystals());
+        assert!(the_game
+            .apply(transporter.clone())?
+            .contains(&GameEvent::PartyReturned {
+                by: LandingCraft::Transporter,
+                crystals: true
+            }));
+        assert_eq!(1, the_game.crystals());
+
+        // The planet only had the one load
+        the_game.apply(transporter)?;
+        assert!(the_game.apply(mine)?.contains(&GameEvent::NoDilithiumFound));
+
+        let energy = the_game.energy;
+        match &the_game.apply(use_crystals.clone())?[..] {
+            [GameEvent::CrystalsUsed { energy: boosted }] => {
+                assert_eq!(energy + the_game.game_defs.initial_energy, *boosted)
+            }
+            [GameEvent::CrystalsFailed { repair_time }] => {
+                assert_eq!(
+                    *repair_time,
+                    the_game.damage.get_damage(Component::WarpEngines)
+                )
+            }
+            events => panic!("expected the crystals to be used, got {:?}", events),
+        }
+        assert_eq!(0, the_game.crystals());
+        assert_eq!(
+            vec![GameEvent::NoCrystals],
+            the_game.validate(&use_crystals)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! # startrust::the_game::probes
+//!
+//! Deep-space probes. A probe flies straight out across the galaxy on the course it is launched
+//! on, reporting each quadrant it passes through until it leaves the galaxy and is lost. It sees
+//! through the long-range sensors, so it can't be launched while they are damaged.
+
+use std::f64::consts::FRAC_PI_4;
+
+use crate::the_game::damage::Component;
+use crate::the_game::GameEvent;
+use crate::{StResult, TheGame};
+
+const LONG_RANGE_SENSORS: Component = Component::LongRangeSensors; // Component #2
+
+/// Explain why a probe can't be launched on this course, if it can't
+pub fn probe_rejection(the_game: &TheGame, course: f64) -> Vec<GameEvent> {
+    let mut events = Vec::new();
+    if the_game.damage.is_damaged(LONG_RANGE_SENSORS.into(), false) {
+        // The probe would be blind
+        the_game.damage.show_damage(&mut events, LONG_RANGE_SENSORS);
+    } else if the_game.probes < 1 {
+        events.push(GameEvent::NoProbesLeft);
+    } else if course.is_nan() || course >= 9.0 {
+        events.push(GameEvent::InvalidOrder);
+    }
+    events
+}
+
+/// Launch a probe, and report what it sees on its way out of the galaxy. A course below 1 aborts
+/// the launch.
+pub fn launch_probe(the_game: &mut TheGame, course: f64) -> StResult<()> {
+    if course < 1.0 {
+        return Ok(());
+    }
+    the_game.probes -= 1;
+    the_game.emit(GameEvent::ProbeLaunched {
+        probes_left: the_game.probes,
+    });
+    let height = the_game.sector_map.height() as f64;
+    let width = the_game.sector_map.width() as f64;
+    let angle = (course - 1.0) * FRAC_PI_4;
+    let (row_step, column_step) = (-angle.sin(), angle.cos());
+    // Measured in sectors across the whole galaxy, one sector a step
+    let mut row = the_game.quadrant.row() as f64 * height + the_game.sector.row() as f64 + 0.5;
+    let mut column =
+        the_game.quadrant.column() as f64 * width + the_game.sector.column() as f64 + 0.5;
+    let mut last = the_game.quadrant;
+    loop {
+        row += row_step;
+        column += column_step;
+        let quadrant = match the_game.quadrant_map.quadrant(
+            (row / height).floor() as i32,
+            (column / width).floor() as i32,
+        ) {
+            Ok(quadrant) => quadrant,
+            Err(_) => break,
+        };
+        if quadrant != last {
+            the_game.quadrant_map[quadrant].show();
+            the_game.record_quadrant(quadrant);
+            the_game.emit(GameEvent::ProbeReport {
+                quadrant,
+                contents: the_game.quadrant_map[quadrant].as_seen(),
+            });
+            last = quadrant;
+        }
+    }
+    the_game.emit(GameEvent::ProbeLost);
+    Ok(())
+} /* End launch_probe */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{new_game, Action, Quadrant};
+
+    #[test]
+    fn test_probe_reveals_its_path() -> StResult<()> {
+        let mut the_game = new_game(2);
+        the_game.start()?;
+        let probes = the_game.probes;
+        let quadrant = the_game.quadrant;
+        let events = the_game.apply(Action::Probe { course: 1.0 })?;
+        assert_eq!(probes - 1, the_game.probes);
+        assert_eq!(
+            Some(&GameEvent::ProbeLaunched {
+                probes_left: probes - 1
+            }),
+            events.first()
+        );
+        assert_eq!(Some(&GameEvent::ProbeLost), events.last());
+
+        // Straight along the row to the edge of the galaxy
+        let reported: Vec<Quadrant> = events
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::ProbeReport { quadrant, .. } => Some(*quadrant),
+                _ => None,
+            })
+            .collect();
+        let expected: Vec<Quadrant> = ((quadrant.column() + 1)..the_game.quadrant_map.width())
+            .map(|column| the_game.quadrant_map.quadrant(quadrant.row(), column))
+            .collect::<StResult<_>>()?;
+        assert_eq!(expected, reported);
+        assert!(reported
+            .iter()
+            .all(|&quadrant| !the_game.records[quadrant].is_hidden()));
+
+        the_game.probes = 0;
+        assert_eq!(
+            vec![GameEvent::NoProbesLeft],
+            the_game.apply(Action::Probe { course: 1.0 })?
+        );
+        the_game
+            .damage
+            .add_damage(Component::LongRangeSensors.into(), 2);
+        assert_eq!(
+            vec![GameEvent::ComponentDamaged {
+                component: Component::LongRangeSensors,
+                repair_time: 2,
+            }],
+            the_game.apply(Action::Probe { course: 1.0 })?
+        );
+        Ok(())
+    }
+}
@@ -1,20 +1,38 @@
+use std::convert::TryFrom;
 use std::fmt::{Display, Formatter};
 use std::ops::{Index, IndexMut};
 
 use log::debug;
+use serde::{Deserialize, Serialize};
 use termcolor::{Color, ColorSpec, WriteColor};
 
 use crate::interaction::draw_number_in_color;
-use crate::the_game::{find_slot, Sector, SectorContents};
-use crate::util::get_random_x_y;
-use crate::{StResult, TheGame};
+use crate::the_game::{
+    find_slot, GameEvent, Klingon, Planet, Romulan, SectorContents, SectorMap, StarDate,
+};
+use crate::util::{get_random_x_y, GameRng};
+use crate::{StResult, StarTrustError, TheGame};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct QuadrantContents {
-    klingons: i32,
+    pub(crate) klingons: i32,
     pub(crate) starbases: i32,
-    stars: i32,
+    pub(crate) stars: i32,
     hidden: bool,
+    /// Romulans, who don't count in the three digits
+    pub(crate) romulans: i32,
+    /// Whether the Romulans here are cloaked, so that scans can't see them
+    pub(crate) cloaked: bool,
+    /// The planet here, if there is one
+    pub(crate) planet: Option<Planet>,
+    /// Black holes here
+    pub(crate) black_holes: i32,
+    /// Sectors of nebula here
+    pub(crate) nebulae: i32,
+    /// Whether a supernova has left the quadrant uninhabitable
+    pub(crate) supernova: bool,
+    /// Until when the remains of the supernova block the way in
+    pub(crate) impassable_until: Option<StarDate>,
 }
 
 impl QuadrantContents {
@@ -24,6 +42,39 @@ impl QuadrantContents {
             starbases,
             stars,
             hidden,
+            romulans: 0,
+            cloaked: false,
+            planet: None,
+            black_holes: 0,
+            nebulae: 0,
+            supernova: false,
+            impassable_until: None,
+        }
+    }
+
+    /// The same contents, with these Romulans in them
+    pub fn with_romulans(mut self, romulans: i32, cloaked: bool) -> Self {
+        self.romulans = romulans;
+        self.cloaked = cloaked;
+        self
+    }
+
+    /// The contents as the sensors see them: shown, but without any cloaked Romulans
+    pub(crate) fn as_seen(&self) -> Self {
+        let mut seen = *self;
+        seen.hidden = false;
+        if seen.cloaked {
+            seen.romulans = 0;
+        }
+        seen
+    }
+
+    /// How many Romulans can be seen here
+    pub fn visible_romulans(&self) -> i32 {
+        if self.cloaked {
+            0
+        } else {
+            self.romulans
         }
     }
 
@@ -40,12 +91,7 @@ impl QuadrantContents {
             klingons,
             quadrant_contents
         );
-        Self {
-            klingons,
-            starbases,
-            stars,
-            hidden,
-        }
+        Self::new(klingons, starbases, stars, hidden)
     }
 
     #[allow(dead_code)]
@@ -79,10 +125,30 @@ impl QuadrantContents {
         assert!(self.starbases <= 1, "starbases: {} > 1", self.starbases);
         assert!(self.stars >= 0, "stars: {} < 0", self.stars);
         assert!(self.stars < 10, "stars: {} >= 10", self.stars);
+        assert!(self.romulans >= 0, "romulans: {} < 0", self.romulans);
+        assert!(self.romulans < 10, "romulans: {} >= 10", self.romulans);
     }
 
+    /// Draw the contents as four characters: an R if Romulans can be seen here (a space if not),
+    /// then the three digits
     pub fn draw<W: WriteColor>(&self, sout: &mut W, bold: bool) -> StResult<()> {
-        if !self.hidden {
+        if !self.hidden && self.visible_romulans() > 0 {
+            let mut color_spec = ColorSpec::new();
+            color_spec.set_fg(Some(Color::Green)).set_bold(bold);
+            sout.set_color(&color_spec)?;
+            write!(sout, "R")?;
+            sout.reset()?;
+        } else {
+            write!(sout, " ")?;
+        }
+        if !self.hidden && self.supernova {
+            let mut color_spec = ColorSpec::new();
+            color_spec.set_fg(Some(Color::Red)).set_bold(bold);
+            sout.set_color(&color_spec)?;
+            write!(sout, "*S*")?;
+            sout.flush()?;
+            sout.reset()?;
+        } else if !self.hidden {
             draw_number_in_color(sout, self.klingons, Color::Magenta, bold)?;
             draw_number_in_color(sout, self.starbases, Color::Cyan, bold)?;
             draw_number_in_color(sout, self.stars, Color::Yellow, bold)?;
@@ -103,57 +169,98 @@ impl Default for QuadrantContents {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct Quadrant(i32, i32, bool);
+/// A quadrant of the galaxy, by row and column from the top left, counting from 0. Only the
+/// quadrant map can say whether a quadrant is inside the galaxy, so it is usually the best place
+/// to get one from (see `QuadrantMap::quadrant`).
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "(i32, i32)", into = "(i32, i32)")]
+pub struct Quadrant(i32, i32);
 
-// TODO: Maybe allow invalid quadrants?
 impl Quadrant {
-    pub(crate) fn new(x: i32, y: i32) -> Self {
-        if x > 7 || y > 7 {
-            debug!(
-                "Could not create quadrant ({}, {}), value out of range",
-                x, y
-            );
-            Self(0, 0, false)
-        } else {
-            Self(x, y, true)
-        }
-    }
-
     fn values(&self) -> (i32, i32) {
         (self.0, self.1)
     }
 
-    pub(crate) fn is_in_range(&self) -> bool {
-        // Original definition: `(q1<0)||(q1>7)||(q2<0)||(q2>7)`
-        // This quadrant can never be out of range, so it's always true
-        self.2
-    }
-
-    pub(crate) fn x(&self) -> i32 {
+    pub fn row(&self) -> i32 {
         self.0
     }
 
-    pub(crate) fn y(&self) -> i32 {
+    pub fn column(&self) -> i32 {
         self.1
     }
 }
 
+impl TryFrom<(i32, i32)> for Quadrant {
+    type Error = StarTrustError;
+
+    fn try_from((row, column): (i32, i32)) -> StResult<Self> {
+        if row < 0 || column < 0 {
+            return Err(StarTrustError::CoordinateError(format!(
+                "quadrant ({}, {}) can't have a negative row or column",
+                row, column
+            )));
+        }
+        Ok(Self(row, column))
+    }
+}
+
+impl From<Quadrant> for (i32, i32) {
+    fn from(quadrant: Quadrant) -> Self {
+        quadrant.values()
+    }
+}
+
 impl Display for Quadrant {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Quadrant({}, {})", self.x(), self.y())
+        write!(f, "Quadrant({}, {})", self.row(), self.column())
     }
 }
 
+#[derive(Deserialize, Serialize)]
 pub struct QuadrantMap {
     quad: Vec<Vec<QuadrantContents>>,
 }
 
 impl QuadrantMap {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(width: i32, height: i32) -> Self {
         Self {
-            quad: vec![vec![QuadrantContents::default(); 8]; 8],
+            quad: vec![vec![QuadrantContents::default(); width as usize]; height as usize],
+        }
+    }
+
+    /// The number of quadrants across the galaxy
+    pub(crate) fn width(&self) -> i32 {
+        self.quad[0].len() as i32
+    }
+
+    /// The number of quadrants down the galaxy
+    pub(crate) fn height(&self) -> i32 {
+        self.quad.len() as i32
+    }
+
+    /// Whether there is a quadrant at these coordinates
+    pub(crate) fn contains(&self, row: i32, column: i32) -> bool {
+        (0..self.height()).contains(&row) && (0..self.width()).contains(&column)
+    }
+
+    /// The quadrant at these coordinates, if it is in the galaxy
+    pub(crate) fn quadrant(&self, row: i32, column: i32) -> StResult<Quadrant> {
+        if !self.contains(row, column) {
+            return Err(StarTrustError::CoordinateError(format!(
+                "quadrant {} - {} is outside of the {}x{} galaxy",
+                row + 1,
+                column + 1,
+                self.width(),
+                self.height()
+            )));
         }
+        Quadrant::try_from((row, column))
+    }
+
+    /// Every quadrant of the galaxy, row by row
+    pub(crate) fn quadrants(&self) -> impl Iterator<Item = Quadrant> {
+        let width = self.width();
+        (0..self.height()).flat_map(move |row| (0..width).map(move |column| Quadrant(row, column)))
     }
 
     pub fn show_quadrant(&mut self, quadrant: Quadrant) {
@@ -178,69 +285,142 @@ impl IndexMut<Quadrant> for QuadrantMap {
 }
 
 /// Setup a quadrant as the ship arrives
-pub fn setup_quadrant(the_game: &mut TheGame) {
-    let quadrant = the_game.current_quadrant();
+pub fn setup_quadrant(the_game: &mut TheGame) -> StResult<()> {
+    let quadrant = the_game.quadrant;
     let s9 = the_game.s9();
     // Set the  global "command" to "None".
     the_game.saved_command = 0.into();
+    the_game.emit(GameEvent::QuadrantEntered { quadrant });
+
+    let quad = &mut the_game.quadrant_map;
+    let n = quad[quadrant];
+    quad[quadrant].validate();
+    debug!("validating quadrant {}", quadrant);
+    quad.show_quadrant(quadrant);
+    let stars = n.stars;
+    let klingons = n.klingons;
+    let starbases = n.starbases;
 
-    let stars: i32;
-    let klingons: i32;
-    let starbases: i32;
-
-    if !quadrant.is_in_range() {
-        stars = 0;
-        klingons = 0;
-        starbases = 0;
-    } else {
-        let quad = &mut the_game.quadrant_map;
-        let n = quad[quadrant];
-        quad[quadrant].validate();
-        debug!("validating quadrant {}", quadrant);
-        quad.show_quadrant(quadrant);
-        stars = n.stars;
-        klingons = n.klingons;
-        starbases = n.starbases;
-    }
-
-    let (x, y) = get_random_x_y();
-    let current_sector = Sector::new(x, y);
-    the_game.set_current_sector(current_sector);
     let sect = &mut the_game.sector_map;
+    let (x, y) = get_random_x_y(&mut the_game.rng, sect.width(), sect.height());
+    let current_sector = sect.sector(x, y)?;
 
-    for i in 0..8 {
-        for j in 0..8 {
-            sect[Sector::new(i, j)] = SectorContents::Empty.into();
-        }
+    for sector in sect.sectors().collect::<Vec<_>>() {
+        sect[sector] = SectorContents::Empty.into();
     }
 
     sect[current_sector] = SectorContents::Enterprise.into();
 
-    let mut ky = y;
-    let mut kx: i32;
-    for i in 0..8 {
-        the_game.k3[i] = 0.0;
-        kx = 8;
-        if (i as i32) < klingons {
-            let sector = find_slot(sect);
-            kx = sector.x();
-            ky = sector.y();
-            sect[sector] = SectorContents::Klingon.into();
-            the_game.k3[i] = s9;
-        }
-        the_game.k1[i] = kx;
-        the_game.k2[i] = ky;
+    // The smallest quadrant always has room for the Klingons and a starbase; anything else that
+    // doesn't fit is left out
+    let no_room =
+        || StarTrustError::GameStateError(format!("quadrant {} is too full to enter", quadrant));
+    the_game.klingons.clear();
+    for _ in 0..klingons {
+        let sector = find_slot(sect, &mut the_game.rng).ok_or_else(no_room)?;
+        sect[sector] = SectorContents::Klingon.into();
+        the_game
+            .klingons
+            .push(Klingon::new(the_game.next_klingon_id, sector, s9));
+        the_game.next_klingon_id += 1;
     }
     if starbases > 0 {
-        let sector = find_slot(sect);
+        let sector = find_slot(sect, &mut the_game.rng).ok_or_else(no_room)?;
         sect[sector] = SectorContents::Starbase.into();
     }
 
-    for _ in 0..stars {
-        let sector = find_slot(sect);
-        sect[sector] = SectorContents::Star.into();
+    let stars = place(sect, &mut the_game.rng, SectorContents::Star, stars);
+
+    // Any quarrel with the Romulans stays in the quadrant it started in
+    the_game.romulans.clear();
+    the_game.romulans_provoked = false;
+    for _ in 0..n.romulans {
+        let sector = match find_slot(sect, &mut the_game.rng) {
+            Some(sector) => sector,
+            None => break,
+        };
+        sect[sector] = SectorContents::Romulan.into();
+        the_game
+            .romulans
+            .push(Romulan::new(sector, the_game.game_defs.romulan_shields));
+    }
+    let mut planet = n.planet;
+    if planet.is_some() && place(sect, &mut the_game.rng, SectorContents::Planet, 1) == 0 {
+        planet = None;
     }
+    let black_holes = place(
+        sect,
+        &mut the_game.rng,
+        SectorContents::BlackHole,
+        n.black_holes,
+    );
+    let nebulae = place(sect, &mut the_game.rng, SectorContents::Nebula, n.nebulae);
+
+    let contents = &mut the_game.quadrant_map[quadrant];
+    contents.stars = stars;
+    contents.romulans = the_game.romulans.len() as i32;
+    contents.planet = planet;
+    contents.black_holes = black_holes;
+    contents.nebulae = nebulae;
+    the_game.record_quadrant(quadrant);
+    the_game.sector = current_sector;
     the_game.quadrant_klingons = klingons;
     the_game.quadrant_starbases = starbases;
     the_game.quadrant_stars = stars;
+    Ok(())
 } /* End setupquad */
+
+/// Put up to `count` of something in empty sectors, and say how many there was room for
+fn place(sect: &mut SectorMap, rng: &mut GameRng, contents: SectorContents, count: i32) -> i32 {
+    for placed in 0..count {
+        match find_slot(sect, rng) {
+            Some(sector) => sect[sector] = contents.into(),
+            None => return placed,
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::TheGameDefsBuilder;
+
+    #[test]
+    fn test_crowded_small_quadrant_leaves_out_what_does_not_fit() -> StResult<()> {
+        let the_game_defs = TheGameDefsBuilder::default()
+            .seed(1)
+            .quadrant_width(4)
+            .quadrant_height(4)
+            .build()
+            .unwrap();
+        let mut the_game = TheGame::new(&the_game_defs);
+        the_game.start()?;
+        let quadrant = the_game.quadrant;
+        let contents = &mut the_game.quadrant_map[quadrant];
+        contents.klingons = 9;
+        contents.starbases = 1;
+        contents.stars = 9;
+        contents.romulans = 9;
+        contents.black_holes = 1;
+        contents.nebulae = 4;
+
+        // Sixteen sectors: the Enterprise, nine Klingons, a starbase and five stars
+        setup_quadrant(&mut the_game)?;
+        assert_eq!(9, the_game.klingons.len());
+        assert_eq!(1, the_game.quadrant_starbases);
+        assert_eq!(5, the_game.quadrant_stars);
+        assert!(the_game.romulans.is_empty());
+        let contents = the_game.quadrant_map[quadrant];
+        assert_eq!(5, contents.stars);
+        assert_eq!(0, contents.romulans);
+        assert_eq!(0, contents.black_holes);
+        assert_eq!(0, contents.nebulae);
+        assert_eq!(5, the_game.records[quadrant].stars);
+        assert!(the_game
+            .sector_map
+            .sectors()
+            .all(|sector| the_game.sector_map.sector_contents_at(sector) != SectorContents::Empty));
+        Ok(())
+    }
+}
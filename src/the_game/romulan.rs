@@ -0,0 +1,180 @@
+//! # startrust::the_game::romulan
+//!
+//! Romulans keep to themselves. They don't count toward saving the Federation, and leave the
+//! Enterprise alone until it attacks or rams one of them. Then every Romulan in the quadrant
+//! drops its cloak and fights until the Enterprise leaves.
+
+use serde::{Deserialize, Serialize};
+
+use crate::the_game::{GameEvent, Sector};
+use crate::util::rnd;
+use crate::{StResult, TheGame};
+
+/// The chance that the Romulans in a quadrant are cloaked when the galaxy is set up
+const CLOAKED_PROBABILITY: f64 = 0.5;
+/// The chance that neutral Romulans cloak or uncloak after each turn
+const CLOAK_CHANGE_PROBABILITY: f64 = 0.2;
+/// What each Romulan destroyed without provocation costs the final rating
+pub(crate) const UNPROVOKED_PENALTY: i32 = 200;
+
+/// A Romulan in the current quadrant
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Romulan {
+    sector: Sector,
+    shields: f64,
+}
+
+impl Romulan {
+    pub fn new(sector: Sector, shields: f64) -> Self {
+        Self { sector, shields }
+    }
+
+    pub fn sector(&self) -> Sector {
+        self.sector
+    }
+
+    pub fn shields(&self) -> f64 {
+        self.shields
+    }
+
+    /// Take energy out of the Romulan's shields, for firing
+    pub(crate) fn drain_shields(&mut self, h: f64) {
+        self.shields -= h;
+    }
+}
+
+/// Scatter Romulans across the galaxy as it is set up
+pub(crate) fn place_romulans(the_game: &mut TheGame) {
+    let frequency = the_game.game_defs.romulan_frequency;
+    for quadrant in the_game.quadrant_map.quadrants().collect::<Vec<_>>() {
+        if rnd(&mut the_game.rng) < frequency {
+            let romulans = 1 + (rnd(&mut the_game.rng) * 2.0).floor() as i32;
+            let cloaked = rnd(&mut the_game.rng) < CLOAKED_PROBABILITY;
+            the_game.quadrant_map[quadrant] =
+                the_game.quadrant_map[quadrant].with_romulans(romulans, cloaked);
+        }
+    }
+}
+
+/// Whether there are Romulans here that will fire on the Enterprise
+pub(crate) fn romulans_hostile(the_game: &TheGame) -> bool {
+    the_game.romulans_provoked && !the_game.romulans.is_empty()
+}
+
+/// Turn the Romulans in the quadrant against the Enterprise
+pub(crate) fn provoke_romulans(the_game: &mut TheGame) {
+    if the_game.romulans_provoked {
+        return;
+    }
+    the_game.romulans_provoked = true;
+    let quadrant = the_game.quadrant;
+    the_game.quadrant_map[quadrant].cloaked = false;
+    if !the_game.romulans.is_empty() {
+        the_game.emit(GameEvent::RomulansProvoked {
+            romulans: the_game.romulans.len() as i32,
+        });
+    }
+}
+
+/// Neutral Romulans sometimes cloak, or drop their cloak
+pub fn romulans_act(the_game: &mut TheGame) -> StResult<()> {
+    if the_game.romulans.is_empty() || the_game.romulans_provoked {
+        return Ok(());
+    }
+    if rnd(&mut the_game.rng) < CLOAK_CHANGE_PROBABILITY {
+        let quadrant = the_game.quadrant;
+        let cloaked = !the_game.quadrant_map[quadrant].cloaked;
+        the_game.quadrant_map[quadrant].cloaked = cloaked;
+        the_game.emit(GameEvent::RomulansCloaked { cloaked });
+    }
+    Ok(())
+} /* End romulans_act */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{new_game, Action, Ending, GameState, SectorContents};
+
+    #[test]
+    fn test_romulans() -> StResult<()> {
+        let mut the_game = new_game(2);
+        the_game.start()?;
+        let quadrant = the_game.quadrant;
+        // Two Romulans lined up to the right of the Enterprise, where a torpedo on course 1 will go
+        let row = the_game.sector.row();
+        let mut romulans = Vec::new();
+        for column in (the_game.sector.column() + 1)..the_game.sector_map.width() {
+            let sector = the_game.sector_map.sector(row, column)?;
+            the_game.sector_map[sector] = SectorContents::Romulan.into();
+            romulans.push(Romulan::new(sector, 600.0));
+        }
+        assert!(romulans.len() > 1, "the Enterprise is too far to the right");
+        the_game.romulans = romulans.clone();
+        the_game.quadrant_map[quadrant] =
+            the_game.quadrant_map[quadrant].with_romulans(romulans.len() as i32, true);
+
+        // Cloaked, they don't show up on the sensors
+        match &the_game.apply(Action::ShortRangeScan)?[..] {
+            [GameEvent::ShortRangeScan(scan)] => assert!(romulans.iter().all(|romulan| {
+                let sector = romulan.sector();
+                scan.sectors[sector.row() as usize][sector.column() as usize]
+                    == SectorContents::Empty
+            })),
+            events => panic!("expected a short range scan, got {:?}", events),
+        }
+        the_game.record_quadrant(quadrant);
+        assert_eq!(0, the_game.records[quadrant].visible_romulans());
+
+        // Torpedoing one is unprovoked, doesn't help against the Klingons, and sets the rest off
+        let total_klingons = the_game.total_klingons;
+        let events = the_game.apply(Action::Torpedo { course: 1.0 })?;
+        assert!(events.contains(&GameEvent::RomulanDestroyed {
+            sector: romulans[0].sector(),
+            provoked: false,
+        }));
+        assert!(events.contains(&GameEvent::RomulansProvoked {
+            romulans: romulans.len() as i32 - 1
+        }));
+        assert_eq!(total_klingons, the_game.total_klingons);
+        assert_eq!(1, the_game.unprovoked_romulan_kills);
+        assert_eq!(romulans.len() - 1, the_game.romulans.len());
+        assert!(!the_game.quadrant_map[quadrant].cloaked);
+        assert!(
+            the_game.is_docked()
+                || events
+                    .iter()
+                    .any(|event| matches!(event, GameEvent::EnterpriseHitByRomulan { .. }))
+        );
+
+        // The next one had it coming
+        let events = the_game.apply(Action::Torpedo { course: 1.0 })?;
+        assert!(events.contains(&GameEvent::RomulanDestroyed {
+            sector: romulans[1].sector(),
+            provoked: true,
+        }));
+        assert_eq!(1, the_game.unprovoked_romulan_kills);
+
+        // The unprovoked kill costs the rating
+        the_game.increment_year();
+        the_game.game_state = GameState::Won;
+        the_game.game_over()?;
+        match the_game.take_events().last() {
+            Some(GameEvent::GameOver {
+                ending:
+                    Ending::Won {
+                        klingons_destroyed,
+                        years,
+                        rating,
+                        unprovoked_romulan_kills: 1,
+                        supernova_kills: 0,
+                    },
+                ..
+            }) => assert_eq!(
+                (*klingons_destroyed as f64 / *years as f64 * 1000.0) as i32 - UNPROVOKED_PENALTY,
+                *rating
+            ),
+            events => panic!("expected a win, got {:?}", events),
+        }
+        Ok(())
+    }
+}
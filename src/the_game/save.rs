@@ -0,0 +1,518 @@
+//! # startrust::the_game::save
+//!
+//! Saved games. A save is JSON, tagged with the version of the format that wrote it, so that saves
+//! from older builds can be recognized and either upgraded or refused.
+
+use std::io::{Read, Write};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::the_game::LandingParty;
+use crate::{StResult, StarTrustError, TheGame, TheGameDefs};
+
+/// The version of the save format written by this build
+pub const SAVE_FORMAT_VERSION: u64 = 13;
+
+#[derive(Serialize)]
+struct SavedGame<'a> {
+    version: u64,
+    game: &'a TheGame,
+}
+
+/// Write the whole state of a game
+pub fn save_game<W: Write>(the_game: &TheGame, writer: W) -> StResult<()> {
+    let saved_game = SavedGame {
+        version: SAVE_FORMAT_VERSION,
+        game: the_game,
+    };
+    serde_json::to_writer(writer, &saved_game)?;
+    Ok(())
+}
+
+/// Read a game written by `save_game`, from this build or an older one
+pub fn load_game<R: Read>(reader: R) -> StResult<TheGame> {
+    let mut saved_game: Value = serde_json::from_reader(reader)?;
+    let version = saved_game
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| StarTrustError::SaveFileError("not a saved game".to_string()))?;
+    if version > SAVE_FORMAT_VERSION {
+        return Err(StarTrustError::SaveFileError(format!(
+            "saved game is version {}, but this build only understands up to version {}",
+            version, SAVE_FORMAT_VERSION
+        )));
+    }
+    if version < 1 {
+        return Err(StarTrustError::SaveFileError(format!(
+            "saved game version {} is no longer supported",
+            version
+        )));
+    }
+    let mut game = saved_game["game"].take();
+    if version < 2 {
+        game = upgrade_from_version_1(game)?;
+    }
+    if version < 3 {
+        game = upgrade_from_version_2(game)?;
+    }
+    if version < 4 {
+        game = upgrade_from_version_3(game)?;
+    }
+    if version < 5 {
+        game = upgrade_from_version_4(game)?;
+    }
+    if version < 6 {
+        game = upgrade_from_version_5(game)?;
+    }
+    if version < 7 {
+        game = upgrade_from_version_6(game)?;
+    }
+    if version < 8 {
+        game = upgrade_from_version_7(game)?;
+    }
+    if version < 9 {
+        game = upgrade_from_version_8(game)?;
+    }
+    if version < 10 {
+        game = upgrade_from_version_9(game)?;
+    }
+    if version < 11 {
+        game = upgrade_from_version_10(game)?;
+    }
+    if version < 12 {
+        game = upgrade_from_version_11(game)?;
+    }
+    if version < 13 {
+        game = upgrade_from_version_12(game)?;
+    }
+    Ok(serde_json::from_value(game)?)
+}
+
+/// Version 1 kept the Enterprise's position in `s1`, `s2`, `q1` and `q2`, and the Klingons' in
+/// `k1` and `k2`, with a row past the bottom of the quadrant for "no Klingon"
+fn upgrade_from_version_1(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 1 save is incomplete".to_string());
+    let field =
+        |game: &Value, name: &str| game.get(name).and_then(Value::as_i64).ok_or_else(bad_save);
+    let sector = json!([field(&game, "s1")?, field(&game, "s2")?]);
+    let quadrant = json!([field(&game, "q1")?, field(&game, "q2")?]);
+    let height = game["sector_map"]["sect"]
+        .as_array()
+        .ok_or_else(bad_save)?
+        .len() as i64;
+    let rows = game["k1"].as_array().ok_or_else(bad_save)?;
+    let columns = game["k2"].as_array().ok_or_else(bad_save)?;
+    let klingon_sectors = rows
+        .iter()
+        .zip(columns)
+        .map(|(row, column)| match (row.as_i64(), column.as_i64()) {
+            (Some(row), Some(column)) if row < height => Ok(json!([row, column])),
+            (Some(_), Some(_)) => Ok(Value::Null),
+            _ => Err(bad_save()),
+        })
+        .collect::<StResult<Vec<_>>>()?;
+
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    for name in ["s1", "s2", "q1", "q2", "k1", "k2"].iter() {
+        fields.remove(*name);
+    }
+    fields.insert("sector".to_string(), sector);
+    fields.insert("quadrant".to_string(), quadrant);
+    fields.insert("klingon_sectors".to_string(), Value::Array(klingon_sectors));
+    Ok(game)
+}
+
+/// Version 2 kept the Klingons' sectors and shields in two lists of eight, with no sector and no
+/// shields left for "no Klingon"
+fn upgrade_from_version_2(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 2 save is incomplete".to_string());
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    let sectors = fields.remove("klingon_sectors").ok_or_else(bad_save)?;
+    let shields = fields.remove("k3").ok_or_else(bad_save)?;
+    let sectors = sectors.as_array().ok_or_else(bad_save)?;
+    let shields = shields.as_array().ok_or_else(bad_save)?;
+    let klingons: Vec<Value> = sectors
+        .iter()
+        .zip(shields)
+        .filter(|(sector, shields)| !sector.is_null() && shields.as_f64().unwrap_or(0.0) > 0.0)
+        .enumerate()
+        .map(|(id, (sector, shields))| json!({"id": id, "sector": sector, "shields": shields}))
+        .collect();
+    fields.insert("next_klingon_id".to_string(), json!(klingons.len()));
+    fields.insert("klingons".to_string(), Value::Array(klingons));
+    Ok(game)
+}
+
+/// Version 3 saves have no separate records; the quadrant map was the record
+fn upgrade_from_version_3(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 3 save is incomplete".to_string());
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    let records = fields.get("quadrant_map").cloned().ok_or_else(bad_save)?;
+    fields.insert("records".to_string(), records);
+    Ok(game)
+}
+
+/// Version 4 saves have no shields; the Enterprise gets them raised and empty
+fn upgrade_from_version_4(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 4 save is incomplete".to_string());
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    fields.insert("shields".to_string(), json!({"up": true, "energy": 0.0}));
+    Ok(game)
+}
+
+/// Version 5 saves keep only the repair times, for six components; damage control is undamaged
+fn upgrade_from_version_5(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 5 save is incomplete".to_string());
+    let mut repair_times = game["damage"].as_array().cloned().ok_or_else(bad_save)?;
+    repair_times.push(json!(0));
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    fields.insert(
+        "damage".to_string(),
+        json!({"repair_times": repair_times, "worst_repair_times": repair_times}),
+    );
+    Ok(game)
+}
+
+/// Version 6 saves have no deep-space probes; the Enterprise gets a full supply
+fn upgrade_from_version_6(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 6 save is incomplete".to_string());
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    fields.insert(
+        "probes".to_string(),
+        json!(TheGameDefs::default().initial_probes),
+    );
+    Ok(game)
+}
+
+/// Version 7 saves have no Romulans; there are none anywhere
+fn upgrade_from_version_7(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 7 save is incomplete".to_string());
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    fields.insert("romulans".to_string(), json!([]));
+    fields.insert("romulans_provoked".to_string(), json!(false));
+    fields.insert("unprovoked_romulan_kills".to_string(), json!(0));
+    add_to_quadrants(
+        &mut game,
+        &[("romulans", json!(0)), ("cloaked", json!(false))],
+    )
+    .ok_or_else(bad_save)?;
+    Ok(game)
+}
+
+/// Version 8 saves have no planets; there are none anywhere, and the landing party is aboard
+fn upgrade_from_version_8(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 8 save is incomplete".to_string());
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    fields.insert("orbiting".to_string(), json!(false));
+    fields.insert("landing_party".to_string(), json!(LandingParty::Aboard));
+    fields.insert("crystals".to_string(), json!(0));
+    add_to_quadrants(&mut game, &[("planet", Value::Null)]).ok_or_else(bad_save)?;
+    Ok(game)
+}
+
+/// Version 9 saves have no black holes or nebulae; there are none anywhere
+fn upgrade_from_version_9(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 9 save is incomplete".to_string());
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    fields.insert("swallowed".to_string(), json!(false));
+    add_to_quadrants(
+        &mut game,
+        &[("black_holes", json!(0)), ("nebulae", json!(0))],
+    )
+    .ok_or_else(bad_save)?;
+    Ok(game)
+}
+
+/// Version 10 saves have no supernovas; none has gone off anywhere
+fn upgrade_from_version_10(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 10 save is incomplete".to_string());
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    fields.insert("caught_in_supernova".to_string(), json!(false));
+    add_to_quadrants(&mut game, &[("supernova", json!(false))]).ok_or_else(bad_save)?;
+    Ok(game)
+}
+
+/// Version 11 saves have no scheduled supernovas; none is due, and no remains block the way
+fn upgrade_from_version_11(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 11 save is incomplete".to_string());
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    fields.insert("supernova_kills".to_string(), json!(0));
+    fields.insert("next_supernova".to_string(), Value::Null);
+    add_to_quadrants(&mut game, &[("impassable_until", Value::Null)]).ok_or_else(bad_save)?;
+    Ok(game)
+}
+
+/// Version 12 saves have no sieges; no starbase is under one, and none has been lost
+fn upgrade_from_version_12(mut game: Value) -> StResult<Value> {
+    let bad_save = || StarTrustError::SaveFileError("version 12 save is incomplete".to_string());
+    let fields = game.as_object_mut().ok_or_else(bad_save)?;
+    fields.insert("siege".to_string(), Value::Null);
+    fields.insert("starbases_lost".to_string(), json!([]));
+    Ok(game)
+}
+
+/// Give every quadrant, in both the quadrant map and the records, these new fields
+fn add_to_quadrants(game: &mut Value, new_fields: &[(&str, Value)]) -> Option<()> {
+    for map in ["quadrant_map", "records"].iter() {
+        for row in game.get_mut(*map)?.get_mut("quad")?.as_array_mut()? {
+            for contents in row.as_array_mut()? {
+                let contents = contents.as_object_mut()?;
+                for (name, value) in new_fields {
+                    contents.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Map;
+
+    use super::*;
+    use crate::{Action, PhaserTargeting, TheGameDefsBuilder};
+
+    #[test]
+    fn test_restored_game_plays_on_identically() -> StResult<()> {
+        let the_game_defs = TheGameDefsBuilder::default().seed(5).build().unwrap();
+        let mut the_game = TheGame::new(&the_game_defs);
+        the_game.start()?;
+        the_game.apply(Action::Warp {
+            course: 3.0,
+            warp: 1.0,
+        })?;
+
+        let mut buffer = Vec::new();
+        save_game(&the_game, &mut buffer)?;
+        let mut restored_game = load_game(buffer.as_slice())?;
+        restored_game.start()?;
+
+        let actions = [
+            Action::Phasers {
+                energy: 200.0,
+                targeting: PhaserTargeting::Even,
+            },
+            Action::Warp {
+                course: 7.5,
+                warp: 2.0,
+            },
+            Action::LongRangeScan,
+        ];
+        for action in actions.iter() {
+            assert_eq!(
+                the_game.apply(action.clone())?,
+                restored_game.apply(action.clone())?
+            );
+        }
+        Ok(())
+    }
+
+    /// One step back through the save format, to what the version before wrote
+    struct Downgrade {
+        /// The version it goes back to
+        version: u64,
+        /// Fields of the game that version didn't have
+        game_fields: &'static [&'static str],
+        /// Fields of each quadrant, in the quadrant map and the records, that version didn't have
+        quadrant_fields: &'static [&'static str],
+        /// Anything else that version laid out differently
+        reshape: Option<fn(&mut Map<String, Value>)>,
+    }
+
+    /// The steps back from the current format, newest first
+    const DOWNGRADES: &[Downgrade] = &[
+        Downgrade {
+            version: 12,
+            game_fields: &["siege", "starbases_lost"],
+            quadrant_fields: &[],
+            reshape: None,
+        },
+        Downgrade {
+            version: 11,
+            game_fields: &["supernova_kills", "next_supernova"],
+            quadrant_fields: &["impassable_until"],
+            reshape: None,
+        },
+        Downgrade {
+            version: 10,
+            game_fields: &["caught_in_supernova"],
+            quadrant_fields: &["supernova"],
+            reshape: None,
+        },
+        Downgrade {
+            version: 9,
+            game_fields: &["swallowed"],
+            quadrant_fields: &["black_holes", "nebulae"],
+            reshape: None,
+        },
+        Downgrade {
+            version: 8,
+            game_fields: &["orbiting", "landing_party", "crystals"],
+            quadrant_fields: &["planet"],
+            reshape: None,
+        },
+        Downgrade {
+            version: 7,
+            game_fields: &["romulans", "romulans_provoked", "unprovoked_romulan_kills"],
+            quadrant_fields: &["romulans", "cloaked"],
+            reshape: None,
+        },
+        Downgrade {
+            version: 6,
+            game_fields: &["probes"],
+            quadrant_fields: &[],
+            reshape: Some(probes_to_version_6),
+        },
+        Downgrade {
+            version: 5,
+            game_fields: &[],
+            quadrant_fields: &[],
+            reshape: Some(damage_to_version_5),
+        },
+        Downgrade {
+            version: 4,
+            game_fields: &["shields"],
+            quadrant_fields: &[],
+            reshape: None,
+        },
+        Downgrade {
+            version: 3,
+            game_fields: &["records"],
+            quadrant_fields: &[],
+            reshape: None,
+        },
+        Downgrade {
+            version: 2,
+            game_fields: &["next_klingon_id"],
+            quadrant_fields: &[],
+            reshape: Some(klingons_to_version_2),
+        },
+        Downgrade {
+            version: 1,
+            game_fields: &[],
+            quadrant_fields: &[],
+            reshape: Some(positions_to_version_1),
+        },
+    ];
+
+    fn probes_to_version_6(game: &mut Map<String, Value>) {
+        game["game_defs"]
+            .as_object_mut()
+            .unwrap()
+            .remove("initial_probes");
+    }
+
+    fn damage_to_version_5(game: &mut Map<String, Value>) {
+        let mut repair_times = game["damage"]["repair_times"].clone();
+        repair_times.as_array_mut().unwrap().truncate(6);
+        game.insert("damage".to_string(), repair_times);
+    }
+
+    fn klingons_to_version_2(game: &mut Map<String, Value>) {
+        let klingons = game.remove("klingons").unwrap();
+        let mut klingon_sectors = vec![Value::Null; 8];
+        let mut k3 = vec![json!(0.0); 8];
+        for (i, klingon) in klingons.as_array().unwrap().iter().enumerate() {
+            klingon_sectors[i] = klingon["sector"].clone();
+            k3[i] = klingon["shields"].clone();
+        }
+        game.insert("klingon_sectors".to_string(), Value::Array(klingon_sectors));
+        game.insert("k3".to_string(), Value::Array(k3));
+    }
+
+    fn positions_to_version_1(game: &mut Map<String, Value>) {
+        let sector = game.remove("sector").unwrap();
+        let quadrant = game.remove("quadrant").unwrap();
+        let klingon_sectors = game.remove("klingon_sectors").unwrap();
+        let klingon_sectors = klingon_sectors.as_array().unwrap();
+        let k1: Vec<Value> = klingon_sectors
+            .iter()
+            .map(|sector| sector.get(0).cloned().unwrap_or_else(|| json!(8)))
+            .collect();
+        let k2: Vec<Value> = klingon_sectors
+            .iter()
+            .map(|sector| sector.get(1).cloned().unwrap_or_else(|| json!(0)))
+            .collect();
+        game.insert("s1".to_string(), sector[0].clone());
+        game.insert("s2".to_string(), sector[1].clone());
+        game.insert("q1".to_string(), quadrant[0].clone());
+        game.insert("q2".to_string(), quadrant[1].clone());
+        game.insert("k1".to_string(), Value::Array(k1));
+        game.insert("k2".to_string(), Value::Array(k2));
+    }
+
+    /// Take a save back one version at a time, and check that each one loads as the same game
+    #[test]
+    fn test_older_saves_are_upgraded() -> StResult<()> {
+        let the_game_defs = TheGameDefsBuilder::default().seed(7).build().unwrap();
+        let mut the_game = TheGame::new(&the_game_defs);
+        the_game.start()?;
+        assert!(!the_game.klingons.is_empty());
+
+        let mut buffer = Vec::new();
+        save_game(&the_game, &mut buffer)?;
+        let mut save: Value = serde_json::from_slice(&buffer)?;
+        for downgrade in DOWNGRADES {
+            let game = save["game"].as_object_mut().unwrap();
+            for name in downgrade.game_fields {
+                game.remove(*name);
+            }
+            for name in downgrade.quadrant_fields {
+                for map in ["quadrant_map", "records"].iter() {
+                    for row in game[*map]["quad"].as_array_mut().unwrap() {
+                        for contents in row.as_array_mut().unwrap() {
+                            contents.as_object_mut().unwrap().remove(*name);
+                        }
+                    }
+                }
+            }
+            if let Some(reshape) = downgrade.reshape {
+                reshape(game);
+            }
+            save["version"] = json!(downgrade.version);
+
+            let restored_game = load_game(serde_json::to_vec(&save)?.as_slice())?;
+            assert_eq!(the_game.sector, restored_game.sector);
+            assert_eq!(the_game.quadrant, restored_game.quadrant);
+            assert_eq!(the_game.klingons, restored_game.klingons);
+            assert_eq!(the_game.next_klingon_id, restored_game.next_klingon_id);
+            assert_eq!(the_game.shields, restored_game.shields);
+            assert_eq!(the_game.probes, restored_game.probes);
+            assert_eq!(the_game.romulans_provoked, restored_game.romulans_provoked);
+            assert_eq!(
+                the_game.unprovoked_romulan_kills,
+                restored_game.unprovoked_romulan_kills
+            );
+            assert_eq!(the_game.orbiting, restored_game.orbiting);
+            assert_eq!(the_game.landing_party, restored_game.landing_party);
+            assert_eq!(the_game.crystals, restored_game.crystals);
+            assert_eq!(the_game.swallowed, restored_game.swallowed);
+            assert_eq!(
+                the_game.caught_in_supernova,
+                restored_game.caught_in_supernova
+            );
+            assert_eq!(the_game.supernova_kills, restored_game.supernova_kills);
+            assert_eq!(the_game.siege, restored_game.siege);
+            assert_eq!(the_game.starbases_lost, restored_game.starbases_lost);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_newer_save_is_rejected() {
+        let save = format!(
+            r#"{{"version": {}, "game": {{}}}}"#,
+            SAVE_FORMAT_VERSION + 1
+        );
+        assert!(matches!(
+            load_game(save.as_bytes()),
+            Err(StarTrustError::SaveFileError(_))
+        ));
+        assert!(matches!(
+            load_game(&b"{}"[..]),
+            Err(StarTrustError::SaveFileError(_))
+        ));
+    }
+}
@@ -1,102 +1,84 @@
-//!
-
-use termcolor::{ColorSpec, WriteColor};
+//! # startrust::the_game::scan
 
 use crate::the_game::damage::Component;
-use crate::the_game::quadrant::Quadrant;
+use crate::the_game::events::ShortRangeScan;
+use crate::the_game::{GameEvent, QuadrantContents, SectorContents};
 use crate::{StResult, TheGame};
 
-/// Set up string for lr scan or galactic records
-fn qstr<W: WriteColor>(
-    the_game: &TheGame,
-    sout: &mut W,
-    i: i32,
-    j: i32,
-    is_current: bool,
-) -> StResult<()> {
-    let quadrant = Quadrant::new(i, j);
-    // The printf format string was "%3.3i", which has a width of 3 digits and has leading 0s.
-    // I _think_.
-    let value = the_game.quadrant_map[quadrant];
-    let emphasize = is_current;
-    value.draw(sout, emphasize)?;
-    Ok(())
-} /* End qstr */
-
 /// Do long-range scan
-pub fn l_range_scan<W: WriteColor>(the_game: &mut TheGame, sout: &mut W) -> StResult<()> {
+pub fn l_range_scan(the_game: &mut TheGame) -> StResult<()> {
     let i = Component::LongRangeSensors; // Component #2
     if the_game.damage.is_damaged(i.into(), false) {
         // Long-range scan inoperative
-        the_game.damage.show_damage(sout, i)?;
+        the_game.damage.show_damage(&mut the_game.events, i);
         return Ok(());
     }
-    let q1: i32 = the_game.q1 as i32;
-    let q2: i32 = the_game.q2 as i32;
-    writeln!(sout, "{} FOR QUADRANT {} - {}", i.as_ref(), q1 + 1, q2 + 1)?;
-    for i in (q1 - 1)..=(q1 + 1) {
-        for j in (q2 - 1)..=(q2 + 1) {
-            write!(sout, "   ")?;
-            sout.flush()?;
-            if (i < 0) || (i > 7) || (j < 0) || (j > 7) {
-                sout.set_color(ColorSpec::new().set_dimmed(true))?;
-                write!(sout, "***")?;
-                sout.flush()?;
-                sout.reset()?;
-            } else {
-                let quadrant = Quadrant::new(i as i32, j as i32);
-                the_game.quadrant_map[quadrant].show();
-                qstr(
-                    the_game,
-                    sout,
-                    i as i32,
-                    j as i32,
-                    the_game.is_current_quadrant(i, j),
-                )?;
+    let current = the_game.quadrant;
+    let mut quadrants = Vec::new();
+    for i in (current.row() - 1)..=(current.row() + 1) {
+        let mut row = Vec::new();
+        for j in (current.column() - 1)..=(current.column() + 1) {
+            match the_game.quadrant_map.quadrant(i, j) {
+                Ok(quadrant)
+                    if quadrant != current && the_game.quadrant_map[quadrant].nebulae > 0 =>
+                {
+                    // The nebula hides whatever else is there
+                    row.push(Some(QuadrantContents::default()));
+                }
+                Ok(quadrant) => {
+                    the_game.quadrant_map[quadrant].show();
+                    the_game.record_quadrant(quadrant);
+                    row.push(Some(the_game.quadrant_map[quadrant].as_seen()));
+                }
+                Err(_) => row.push(None),
             }
         }
-        writeln!(sout)?;
+        quadrants.push(row);
     }
+    the_game.emit(GameEvent::LongRangeScan {
+        quadrant: (current.row() + 1, current.column() + 1),
+        quadrants,
+    });
     Ok(())
 } /* End lrscan */
 
 /// Do galactic records
-pub fn galactic_records<W: WriteColor>(the_game: &TheGame, sout: &mut W) -> StResult<()> {
+pub fn galactic_records(the_game: &mut TheGame) -> StResult<()> {
     let i = Component::GalacticRecords; // Component #5
     if the_game.damage.is_damaged(i.into(), false) {
         // Galactic records inoperative
-        the_game.damage.show_damage(sout, i)?;
+        the_game.damage.show_damage(&mut the_game.events, i);
         return Ok(());
     }
-    writeln!(
-        sout,
-        "CUMULATIVE GALACTIC MAP FOR STARDATE {}",
-        the_game.current_stardate
-    )?;
-    for i in 0..8 {
-        for j in 0..8 {
-            write!(sout, "  ")?;
-            sout.flush()?;
-            qstr(
-                the_game,
-                sout,
-                i as i32,
-                j as i32,
-                the_game.is_current_quadrant(i, j),
-            )?;
-        }
-        writeln!(sout)?;
-    }
+    // The Enterprise can see its own quadrant, but the rest is only as fresh as the last look
+    the_game.record_quadrant(the_game.quadrant);
+    let width = the_game.records.width() as usize;
+    let quadrants = the_game
+        .records
+        .quadrants()
+        .map(|quadrant| the_game.records[quadrant])
+        .collect::<Vec<_>>()
+        .chunks(width)
+        .map(|row| row.to_vec())
+        .collect();
+    the_game.emit(GameEvent::GalacticRecords {
+        stardate: the_game.current_stardate,
+        quadrants,
+        current: (
+            the_game.quadrant.row() as usize,
+            the_game.quadrant.column() as usize,
+        ),
+    });
     Ok(())
 } /* End galrecs */
 
 /// Do short-range scan
-pub fn s_range_scan<W: WriteColor>(the_game: &mut TheGame, sout: &mut W, a: i32) -> StResult<()> {
+pub fn s_range_scan(the_game: &mut TheGame, a: i32) -> StResult<()> {
     the_game.check_condition(); //?
     if a == 0
     /* Initial entry into quadrant */
     {
-        the_game.check_for_hits(sout)?;
+        the_game.check_for_hits()?;
         if the_game.energy <= 0.0 {
             /* Ran out of energy! */
             return Ok(());
@@ -105,55 +87,75 @@ pub fn s_range_scan<W: WriteColor>(the_game: &mut TheGame, sout: &mut W, a: i32)
     let i = Component::ShortRangeSensors; // Component #1
     if the_game.damage.is_damaged(i.into(), false) {
         // Short-range scan inoperative
-        the_game.damage.show_damage(sout, i)?;
+        the_game.damage.show_damage(&mut the_game.events, i);
         return Ok(());
     }
-    for i in 0..8 {
-        for j in 0..8 {
-            write!(sout, "{} ", the_game.sector_map.sector_char_at_coords(i, j))?;
-            sout.flush()?;
-        }
-        write!(sout, "  ")?;
-        sout.flush()?;
-        match i {
-            0 => {
-                writeln!(
-                    sout,
-                    "YEARS = {}",
-                    the_game.game_defs.ending_stardate - the_game.current_stardate
-                )?;
-            }
-            1 => {
-                writeln!(sout, "STARDATE = {}", the_game.current_stardate)?;
-            }
-            2 => {
-                write!(sout, "CONDITION: ")?;
-                sout.set_color(&the_game.current_condition.get_color_spec())?;
-                writeln!(sout, "{}", the_game.current_condition.as_ref())?;
-                sout.reset()?;
-            }
-            3 => {
-                writeln!(sout, "QUADRANT = {} - {}", the_game.q1 + 1, the_game.q2 + 1)?;
-            }
-            4 => {
-                writeln!(sout, "SECTOR = {} - {}", the_game.s1 + 1, the_game.s2 + 1)?;
-            }
-            5 => {
-                writeln!(sout, "ENERGY = {:03}", the_game.energy)?; // printf format string was "%.3f"
-            }
-            6 => {
-                writeln!(
-                    sout,
-                    "{} = {}",
-                    Component::PhotonTorpedoes.as_ref(),
-                    the_game.photo_torpedoes
-                )?;
-            }
-            7 => {
-                writeln!(sout, "KLINGONS LEFT = {}", the_game.total_klingons)?;
+    let width = the_game.sector_map.width() as usize;
+    let cloaked = the_game.quadrant_map[the_game.quadrant].cloaked;
+    let sectors = the_game
+        .sector_map
+        .sectors()
+        .map(
+            |sector| match the_game.sector_map.sector_contents_at(sector) {
+                SectorContents::Romulan if cloaked => SectorContents::Empty,
+                contents => contents,
+            },
+        )
+        .collect::<Vec<_>>()
+        .chunks(width)
+        .map(|row| row.to_vec())
+        .collect();
+    let scan = ShortRangeScan {
+        sectors,
+        years_left: the_game.game_defs.ending_stardate - the_game.current_stardate,
+        stardate: the_game.current_stardate,
+        condition: the_game.current_condition,
+        quadrant: (the_game.quadrant.row() + 1, the_game.quadrant.column() + 1),
+        sector: (the_game.sector.row() + 1, the_game.sector.column() + 1),
+        energy: the_game.energy,
+        photon_torpedoes: the_game.photo_torpedoes,
+        klingons_left: the_game.total_klingons,
+        shields: the_game.shields.energy(),
+        shields_up: the_game.shields.is_up(),
+    };
+    the_game.emit(GameEvent::ShortRangeScan(scan));
+    Ok(())
+} /* End srscan */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{Action, TheGameDefsBuilder};
+
+    #[test]
+    fn test_scans_match_galaxy_size() -> StResult<()> {
+        let the_game_defs = TheGameDefsBuilder::default()
+            .seed(3)
+            .galaxy_width(5)
+            .galaxy_height(3)
+            .quadrant_width(6)
+            .quadrant_height(4)
+            .build()
+            .unwrap();
+        let mut the_game = TheGame::new(&the_game_defs);
+        let events = the_game.start()?;
+        let scan = events
+            .iter()
+            .find_map(|event| match event {
+                GameEvent::ShortRangeScan(scan) => Some(scan),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(4, scan.sectors.len());
+        assert!(scan.sectors.iter().all(|row| row.len() == 6));
+
+        match the_game.apply(Action::GalacticRecords)?.as_slice() {
+            [GameEvent::GalacticRecords { quadrants, .. }] => {
+                assert_eq!(3, quadrants.len());
+                assert!(quadrants.iter().all(|row| row.len() == 5));
             }
-            _ => {}
+            events => panic!("unexpected events {:?}", events),
         }
+        Ok(())
     }
-    Ok(())
-} /* End srscan */
+}
@@ -1,58 +1,94 @@
+use std::convert::TryFrom;
 use std::ops::{Index, IndexMut};
 
 use num_enum::{FromPrimitive, IntoPrimitive};
+use serde::{Deserialize, Serialize};
+use strum_macros::AsRefStr;
 
-use crate::util::get_random_x_y;
+use crate::util::{get_random_x_y, GameRng};
 #[allow(unused_imports)]
 use crate::TheGame;
+use crate::{StResult, StarTrustError};
 
 // This has to be a byte string not a `str` because Rust worries about UTF-8 (very reasonably)
-const QS: &[u8] = b"U.EKB*";
+const QS: &[u8] = b"U.EKB*R@O#";
 
-///
-#[derive(Copy, Clone, Debug, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
+/// What can be found in a sector
+#[derive(AsRefStr, Copy, Clone, Debug, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
 #[repr(i32)]
 pub enum SectorContents {
     #[num_enum(default)]
+    #[strum(serialize = "UNKNOWN")]
     Unknown = 0,
+    #[strum(serialize = "EMPTY SPACE")]
     Empty = 1,
+    #[strum(serialize = "ENTERPRISE")]
     Enterprise = 2,
+    #[strum(serialize = "KLINGON")]
     Klingon = 3,
+    #[strum(serialize = "STARBASE")]
     Starbase = 4,
+    #[strum(serialize = "STAR")]
     Star = 5,
+    #[strum(serialize = "ROMULAN")]
+    Romulan = 6,
+    #[strum(serialize = "PLANET")]
+    Planet = 7,
+    #[strum(serialize = "BLACK HOLE")]
+    BlackHole = 8,
+    #[strum(serialize = "NEBULA")]
+    Nebula = 9,
 }
 
 impl SectorContents {
-    pub fn to_char(&self) -> char {
-        let index: i32 = (*self).into();
+    pub fn to_char(self) -> char {
+        let index: i32 = self.into();
         QS[index as usize] as char
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+/// A sector of a quadrant, by row and column from the top left, counting from 0. Only the sector
+/// map can say whether a sector is inside the quadrant, so it is usually the best place to get
+/// one from (see `SectorMap::sector`).
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "(i32, i32)", into = "(i32, i32)")]
 pub struct Sector(i32, i32);
 
 impl Sector {
-    pub(crate) fn new(x: i32, y: i32) -> Self {
-        if x > 7 || y > 7 {
-            panic!("Could not create sector ({}, {}), value out of range", x, y)
-        }
-        Self(x, y)
-    }
-
     fn values(&self) -> (i32, i32) {
         (self.0, self.1)
     }
 
-    pub(crate) fn x(&self) -> i32 {
+    pub fn row(&self) -> i32 {
         self.0
     }
 
-    pub(crate) fn y(&self) -> i32 {
+    pub fn column(&self) -> i32 {
         self.1
     }
 }
 
+impl TryFrom<(i32, i32)> for Sector {
+    type Error = StarTrustError;
+
+    fn try_from((row, column): (i32, i32)) -> StResult<Self> {
+        if row < 0 || column < 0 {
+            return Err(StarTrustError::CoordinateError(format!(
+                "sector ({}, {}) can't have a negative row or column",
+                row, column
+            )));
+        }
+        Ok(Self(row, column))
+    }
+}
+
+impl From<Sector> for (i32, i32) {
+    fn from(sector: Sector) -> Self {
+        sector.values()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
 pub struct SectorMap {
     sect: Vec<Vec<i32>>,
 }
@@ -74,34 +110,89 @@ impl IndexMut<Sector> for SectorMap {
 }
 
 impl SectorMap {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(width: i32, height: i32) -> Self {
         Self {
-            sect: vec![vec![0i32; 8]; 8],
+            sect: vec![vec![0i32; width as usize]; height as usize],
         }
     }
 
-    pub(crate) fn sector_contents_at(&self, sector: Sector) -> SectorContents {
-        self[sector].into()
+    /// The number of sectors across a quadrant
+    pub(crate) fn width(&self) -> i32 {
+        self.sect[0].len() as i32
     }
 
-    pub(crate) fn sector_contents_at_coords(&self, x: i32, y: i32) -> SectorContents {
-        let sector = Sector::new(x, y);
-        self.sector_contents_at(sector)
+    /// The number of sectors down a quadrant
+    pub(crate) fn height(&self) -> i32 {
+        self.sect.len() as i32
     }
 
-    pub(crate) fn sector_char_at_coords(&self, x: i32, y: i32) -> char {
-        let index = self.sector_contents_at_coords(x, y);
-        index.to_char()
+    /// Whether there is a sector at these coordinates
+    pub(crate) fn contains(&self, row: i32, column: i32) -> bool {
+        (0..self.height()).contains(&row) && (0..self.width()).contains(&column)
+    }
+
+    /// The sector at these coordinates, if it is in the quadrant
+    pub(crate) fn sector(&self, row: i32, column: i32) -> StResult<Sector> {
+        if !self.contains(row, column) {
+            return Err(StarTrustError::CoordinateError(format!(
+                "sector {} - {} is outside of the {}x{} quadrant",
+                row + 1,
+                column + 1,
+                self.width(),
+                self.height()
+            )));
+        }
+        Sector::try_from((row, column))
+    }
+
+    /// Every sector of the quadrant, row by row
+    pub(crate) fn sectors(&self) -> impl Iterator<Item = Sector> {
+        let width = self.width();
+        (0..self.height()).flat_map(move |row| (0..width).map(move |column| Sector(row, column)))
+    }
+
+    pub(crate) fn sector_contents_at(&self, sector: Sector) -> SectorContents {
+        self[sector].into()
     }
 }
 
-/// Find an unoccupied sector
-pub fn find_slot(sector_map: &SectorMap) -> Sector {
+/// Find an unoccupied sector, if there are any left
+pub fn find_slot(sector_map: &SectorMap, rng: &mut GameRng) -> Option<Sector> {
+    if sector_map
+        .sectors()
+        .all(|sector| sector_map.sector_contents_at(sector) != SectorContents::Empty)
+    {
+        return None;
+    }
     loop {
-        let (x, y) = get_random_x_y();
-        let sector = Sector::new(x, y);
+        let (x, y) = get_random_x_y(rng, sector_map.width(), sector_map.height());
+        let sector = Sector(x, y);
         if sector_map.sector_contents_at(sector) == SectorContents::Empty {
-            return sector;
+            return Some(sector);
         }
     }
 } /* End findslot */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sector_coordinates_are_checked() {
+        assert_eq!(Sector::try_from((2, 5)).unwrap().row(), 2);
+        assert_eq!(Sector::try_from((2, 5)).unwrap().column(), 5);
+        assert!(matches!(
+            Sector::try_from((-1, 5)),
+            Err(StarTrustError::CoordinateError(_))
+        ));
+
+        let sector_map = SectorMap::new(6, 4);
+        assert!(sector_map.sector(3, 5).is_ok());
+        assert!(matches!(
+            sector_map.sector(4, 0),
+            Err(StarTrustError::CoordinateError(_))
+        ));
+        assert!(sector_map.sector(0, 6).is_err());
+        assert_eq!(sector_map.sectors().count(), 24);
+    }
+}
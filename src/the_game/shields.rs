@@ -0,0 +1,119 @@
+//! # startrust::the_game::shields
+
+use serde::{Deserialize, Serialize};
+
+use crate::the_game::GameEvent;
+use crate::{StResult, TheGame};
+
+/// The Enterprise's deflector shields, with their own store of energy
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Shields {
+    up: bool,
+    energy: f64,
+}
+
+impl Default for Shields {
+    /// Raised, but with nothing in them until energy is transferred
+    fn default() -> Self {
+        Self {
+            up: true,
+            energy: 0.0,
+        }
+    }
+}
+
+impl Shields {
+    pub(crate) fn is_up(&self) -> bool {
+        self.up
+    }
+
+    pub(crate) fn energy(&self) -> f64 {
+        self.energy
+    }
+
+    /// Soak up as much of a hit as the shields can, returning what gets through to the ship
+    pub(crate) fn absorb(&mut self, hit: f64) -> f64 {
+        if !self.up {
+            return hit;
+        }
+        let absorbed = hit.min(self.energy);
+        self.energy -= absorbed;
+        hit - absorbed
+    }
+}
+
+/// Explain why the shields can't be set this way, if they can't
+pub fn shields_rejection(the_game: &TheGame, transfer: f64) -> Vec<GameEvent> {
+    let mut events = Vec::new();
+    if transfer.is_nan() {
+        events.push(GameEvent::InvalidOrder);
+    } else if transfer > the_game.energy {
+        events.push(GameEvent::NotEnoughEnergy {
+            energy: the_game.energy,
+        });
+    } else if -transfer > the_game.shields.energy {
+        events.push(GameEvent::NotEnoughShieldEnergy {
+            shields: the_game.shields.energy,
+        });
+    }
+    events
+}
+
+/// Raise or lower the shields, and move energy into them from the main reserves (or back out,
+/// for a negative transfer)
+pub fn shields(the_game: &mut TheGame, up: bool, transfer: f64) -> StResult<()> {
+    the_game.energy -= transfer;
+    the_game.shields.energy += transfer;
+    the_game.shields.up = up;
+    the_game.emit(GameEvent::ShieldsSet {
+        up,
+        shields: the_game.shields.energy,
+        energy: the_game.energy,
+    });
+    Ok(())
+} /* End shields */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{new_game, Action};
+
+    #[test]
+    fn test_hits_drain_shields_first() -> StResult<()> {
+        let mut the_game = new_game(7);
+        the_game.start()?;
+        assert!(!the_game.klingons.is_empty() && !the_game.is_docked());
+        let action = Action::Shields {
+            up: true,
+            transfer: 1000.0,
+        };
+        assert!(the_game.validate(&action).is_empty());
+        the_game.apply(action)?;
+        let energy = the_game.energy;
+        the_game.check_for_hits()?;
+        assert_eq!(energy, the_game.energy);
+        assert!(the_game.shields.energy() < 1000.0);
+
+        // Lowered, they let everything through
+        let shields_left = the_game.shields.energy();
+        the_game.apply(Action::Shields {
+            up: false,
+            transfer: 0.0,
+        })?;
+        the_game.check_for_hits()?;
+        assert!(the_game.energy < energy);
+        assert_eq!(shields_left, the_game.shields.energy());
+
+        let too_much = Action::Shields {
+            up: true,
+            transfer: -(shields_left + 1.0),
+        };
+        assert_eq!(
+            vec![GameEvent::NotEnoughShieldEnergy {
+                shields: shields_left
+            }],
+            the_game.validate(&too_much)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! # startrust::the_game::siege
+//!
+//! Klingons besieging starbases. Now and then the Klingons in a quadrant with a starbase lay siege
+//! to it, and the starbase sends out a distress call with the stardate it can hold out until. If
+//! the Enterprise gets there first the Klingons break off; otherwise the starbase is destroyed, and
+//! there is one fewer place to dock.
+
+use serde::{Deserialize, Serialize};
+
+use crate::the_game::{GameEvent, Quadrant, StarDate};
+use crate::util::rnd;
+use crate::TheGame;
+
+/// The chance each stardate that Klingons at a starbase lay siege to it, when none is under siege
+const SIEGE_PROBABILITY: f64 = 0.2;
+/// How long a besieged starbase holds out, in years
+const SIEGE_YEARS: i32 = 4;
+
+/// A starbase under attack, and how long it can hold out
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Siege {
+    quadrant: Quadrant,
+    deadline: StarDate,
+}
+
+/// Press or lift the siege of a starbase as a stardate passes, or start one if none is under way
+pub(crate) fn besiege_starbases(the_game: &mut TheGame) {
+    if let Some(siege) = the_game.siege {
+        let contents = the_game.quadrant_map[siege.quadrant];
+        if contents.starbases == 0 || contents.klingons == 0 {
+            // Something else got there first, or the Klingons moved on
+            the_game.siege = None;
+        } else if the_game.current_stardate >= siege.deadline {
+            the_game.siege = None;
+            the_game.quadrant_map[siege.quadrant].starbases -= 1;
+            the_game.total_starbases -= 1;
+            the_game.starbases_lost.push(siege.quadrant);
+            // Starfleet knows it is gone, even if the Enterprise hasn't seen it
+            the_game.records[siege.quadrant].starbases = 0;
+            the_game.emit(GameEvent::StarbaseLost {
+                quadrant: siege.quadrant,
+            });
+        }
+        return;
+    }
+    let targets = the_game
+        .quadrant_map
+        .quadrants()
+        .filter(|&quadrant| {
+            let contents = the_game.quadrant_map[quadrant];
+            quadrant != the_game.quadrant && contents.starbases > 0 && contents.klingons > 0
+        })
+        .collect::<Vec<_>>();
+    if targets.is_empty() || rnd(&mut the_game.rng) > SIEGE_PROBABILITY {
+        return;
+    }
+    let quadrant = targets[(rnd(&mut the_game.rng) * targets.len() as f64).floor() as usize];
+    let siege = Siege {
+        quadrant,
+        deadline: the_game.current_stardate + SIEGE_YEARS,
+    };
+    the_game.siege = Some(siege);
+    the_game.emit(GameEvent::StarbaseUnderSiege {
+        quadrant,
+        deadline: siege.deadline,
+    });
+} /* End besiege_starbases */
+
+/// The Enterprise has arrived in a quadrant, which breaks any siege there
+pub(crate) fn relieve_siege(the_game: &mut TheGame) {
+    if let Some(siege) = the_game.siege {
+        if siege.quadrant == the_game.quadrant {
+            the_game.siege = None;
+            the_game.emit(GameEvent::SiegeRelieved {
+                quadrant: siege.quadrant,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::new_game;
+    use crate::StResult;
+
+    /// Put a starbase with Klingons at it somewhere away from the Enterprise
+    fn besieged_quadrant(the_game: &mut TheGame) -> Quadrant {
+        let quadrant = the_game
+            .quadrant_map
+            .quadrants()
+            .find(|&quadrant| {
+                quadrant != the_game.quadrant && !the_game.quadrant_map[quadrant].supernova
+            })
+            .unwrap();
+        let contents = &mut the_game.quadrant_map[quadrant];
+        if contents.starbases == 0 {
+            contents.starbases = 1;
+            the_game.total_starbases += 1;
+        }
+        if contents.klingons == 0 {
+            contents.klingons = 1;
+            the_game.total_klingons += 1;
+        }
+        the_game.siege = Some(Siege {
+            quadrant,
+            deadline: the_game.current_stardate + SIEGE_YEARS,
+        });
+        quadrant
+    }
+
+    #[test]
+    fn test_unrelieved_starbase_is_lost() -> StResult<()> {
+        let mut the_game = new_game(1);
+        the_game.start()?;
+        let quadrant = besieged_quadrant(&mut the_game);
+        let total_starbases = the_game.total_starbases;
+
+        // It holds out until the deadline
+        the_game.current_stardate += SIEGE_YEARS - 1;
+        besiege_starbases(&mut the_game);
+        assert!(the_game.take_events().is_empty());
+        assert_eq!(1, the_game.quadrant_map[quadrant].starbases);
+
+        the_game.current_stardate += 1;
+        besiege_starbases(&mut the_game);
+        assert_eq!(
+            vec![GameEvent::StarbaseLost { quadrant }],
+            the_game.take_events()
+        );
+        assert_eq!(0, the_game.quadrant_map[quadrant].starbases);
+        assert_eq!(total_starbases - 1, the_game.total_starbases);
+        assert_eq!(vec![quadrant], the_game.starbases_lost);
+        assert_eq!(None, the_game.siege);
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrival_relieves_starbase() -> StResult<()> {
+        let mut the_game = new_game(1);
+        the_game.start()?;
+        let quadrant = besieged_quadrant(&mut the_game);
+
+        the_game.quadrant = quadrant;
+        relieve_siege(&mut the_game);
+        assert_eq!(
+            vec![GameEvent::SiegeRelieved { quadrant }],
+            the_game.take_events()
+        );
+        assert_eq!(None, the_game.siege);
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
 use std::fmt::{Display, Formatter};
 use std::ops;
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Default)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Default, Serialize)]
 pub struct StarDate(i32);
 
 impl StarDate {
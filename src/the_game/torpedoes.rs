@@ -1,69 +1,172 @@
-//!
+//! # startrust::the_game::torpedoes
 
-use std::io::BufRead;
-
-use termcolor::WriteColor;
-
-use crate::interaction::beep;
-use crate::interaction::getcourse;
 use crate::the_game::commands::Command;
+use crate::the_game::damage::Component;
 use crate::the_game::path::do_path;
-use crate::the_game::GameState;
+use crate::the_game::{GameEvent, GameState};
 use crate::{StResult, TheGame};
 
-pub fn do_torpedoes<R: BufRead, W: WriteColor>(
-    the_game: &mut TheGame,
-    sin: &mut R,
-    sout: &mut W,
-    command: &mut Command,
-    gamecomp: &mut GameState,
-) -> StResult<()> {
+/// The most torpedoes that can be fired in one spread
+pub const MAX_SPREAD: usize = 3;
+
+/// The courses for a fan of torpedoes centred on `course`, `angle` apart (in course units)
+pub fn fan(course: f64, count: usize, angle: f64) -> Vec<f64> {
+    let middle = (count as f64 - 1.0) / 2.0;
+    (0..count)
+        .map(|i| {
+            let c = course + (i as f64 - middle) * angle;
+            // Round the compass, so that 0.5 is 8.5
+            (c - 1.0).rem_euclid(8.0) + 1.0
+        })
+        .collect()
+}
+
+/// Explain why a torpedo can't be fired on this course, if it can't
+pub fn torpedo_rejection(the_game: &TheGame, course: f64) -> Vec<GameEvent> {
+    let mut events = Vec::new();
     if the_game.damage.is_damaged(4, false) {
         // Torpedoes damaged
-        write!(sout, "SPACE CRUD BLOCKING TUBES.  ")?;
-        sout.flush()?;
-        let i = 4;
-        the_game.damage.show_est_repair_time(sout, i)?;
-        beep();
-        return Ok(());
+        events.push(GameEvent::TubesBlocked {
+            repair_time: the_game.damage.get_damage(Component::PhotonTorpedoes),
+        });
+    } else if the_game.photo_torpedoes < 1 {
+        events.push(GameEvent::NoTorpedoesLeft);
+    } else if course.is_nan() || course >= 9.0 {
+        events.push(GameEvent::InvalidOrder);
     }
-    let n: f64 = 15.0;
-    if the_game.photo_torpedoes < 1 {
-        writeln!(sout, "NO TORPEDOES LEFT!")?;
-        return Ok(());
-    }
-    the_game.course = 10.0;
-    while the_game.course >= 9.0 {
-        write!(sout, "TORPEDO ")?;
-        sout.flush()?;
+    events
+}
 
-        the_game.course = getcourse(sin, sout)?;
+/// Explain why a spread of torpedoes can't be fired on these courses, if it can't
+pub fn spread_rejection(the_game: &TheGame, courses: &[f64]) -> Vec<GameEvent> {
+    let mut events = torpedo_rejection(the_game, 1.0);
+    if !events.is_empty() {
+        return events;
     }
-    if the_game.course < 1.0 {
+    if courses.is_empty()
+        || courses.len() > MAX_SPREAD
+        || courses.iter().any(|&course| !(1.0..9.0).contains(&course))
+    {
+        events.push(GameEvent::InvalidOrder);
+    } else if courses.len() as i32 > the_game.photo_torpedoes {
+        events.push(GameEvent::NotEnoughTorpedoes {
+            torpedoes: the_game.photo_torpedoes,
+        });
+    }
+    events
+}
+
+pub fn do_torpedoes(the_game: &mut TheGame, course: f64) -> StResult<()> {
+    if course < 1.0 {
         // Abort firing of torpedo
+        the_game.course = course;
         return Ok(());
     }
-    the_game.photo_torpedoes -= 1;
-    write!(sout, "TRACK: ")?;
-    sout.flush()?;
-    do_path(the_game, sout, *command, n)?;
-    *command = the_game.saved_command;
-    // let i = n;
+    do_spread(the_game, &[course])
+}
+
+/// Fire torpedoes on each of the courses, one after another, before the Klingons can answer
+pub fn do_spread(the_game: &mut TheGame, courses: &[f64]) -> StResult<()> {
+    // Far enough to cross the quadrant on any course
+    let n = (the_game.sector_map.width() + the_game.sector_map.height() - 1) as f64;
+    for &course in courses {
+        if the_game.new_quadrant || the_game.game_state.is_done() {
+            // A supernova has put an end to the spread
+            break;
+        }
+        the_game.course = course;
+        the_game.photo_torpedoes -= 1;
+        the_game.emit(GameEvent::TorpedoFired);
+        do_path(the_game, Command::PhotonTorpedos, n)?;
+    }
     if the_game.energy <= 0.0 {
         /* Ran out of energy */
-        *gamecomp = (-1).into();
+        the_game.game_state = GameState::Lost;
     }
-    the_game.check_for_hits(sout)?;
+    the_game.check_for_hits()?;
     if the_game.energy <= 0.0 {
         /* Ran out of energy */
-        *gamecomp = (-1).into();
+        the_game.game_state = GameState::Lost;
     }
     if the_game.total_klingons < 1 {
         /* All Klingons destroyed! */
-        *gamecomp = 1.into();
+        the_game.game_state = GameState::Won;
     }
-    if !gamecomp.is_done() {
+    if !the_game.game_state.is_done() {
         the_game.check_condition();
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{new_game, Action, ComputerRequest};
+
+    #[test]
+    fn test_torpedo_rejects_nan() -> StResult<()> {
+        let mut the_game = new_game(1);
+        the_game.start()?;
+        the_game.damage.fix_damage();
+        let torpedoes = the_game.photo_torpedoes;
+        let events = the_game.apply(Action::Torpedo { course: f64::NAN })?;
+        assert_eq!(vec![GameEvent::InvalidOrder], events);
+        assert_eq!(torpedoes, the_game.photo_torpedoes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_torpedo_spread() -> StResult<()> {
+        assert_eq!(vec![8.5, 1.0, 1.5], fan(1.0, 3, 0.5));
+
+        let mut the_game = new_game(23);
+        the_game.start()?;
+        // The computer may have been hit on the way in
+        the_game.damage.fix_damage();
+        let torpedoes = the_game.photo_torpedoes;
+        let courses: Vec<f64> =
+            match &the_game.apply(Action::Computer(ComputerRequest::TorpedoData))?[..] {
+                [GameEvent::TorpedoData { targets }] => targets
+                    .iter()
+                    .take(MAX_SPREAD)
+                    .map(|target| target.course)
+                    .collect(),
+                events => panic!("expected torpedo data, got {:?}", events),
+            };
+        assert_eq!(MAX_SPREAD, courses.len());
+        let events = the_game.apply(Action::TorpedoSpread { courses })?;
+        assert_eq!(torpedoes - 3, the_game.photo_torpedoes);
+        let fired: Vec<usize> = events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| matches!(event, GameEvent::TorpedoFired))
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(3, fired.len());
+
+        // The Klingons left answer once, after the whole salvo
+        let hits: Vec<usize> = events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| matches!(event, GameEvent::EnterpriseHit { .. }))
+            .map(|(i, _)| i)
+            .collect();
+        assert!(hits.len() <= the_game.klingons.len());
+        assert!(hits.iter().all(|&i| i > fired[2]));
+
+        the_game.photo_torpedoes = 2;
+        assert_eq!(
+            vec![GameEvent::NotEnoughTorpedoes { torpedoes: 2 }],
+            the_game.apply(Action::TorpedoSpread {
+                courses: vec![1.0, 2.0, 3.0]
+            })?
+        );
+        assert_eq!(
+            vec![GameEvent::InvalidOrder],
+            the_game.apply(Action::TorpedoSpread {
+                courses: vec![1.0, 9.5]
+            })?
+        );
+        Ok(())
+    }
+}
@@ -1,90 +1,88 @@
-//!
+//! # startrust::the_game::warp
 
 use std::convert::TryInto;
-use std::io::BufRead;
 
-use termcolor::WriteColor;
-
-use crate::interaction::beep;
-use crate::interaction::{delay, getcourse, getwarp};
 use crate::the_game::commands::Command;
-use crate::the_game::damage::Component;
+use crate::the_game::damage::{Component, COMPONENTS};
+use crate::the_game::migration::migrate_klingons;
+use crate::the_game::nova::scheduled_supernova;
 use crate::the_game::path::do_path;
-use crate::the_game::GameState;
+use crate::the_game::planets::LandingParty;
+use crate::the_game::siege::besiege_starbases;
+use crate::the_game::{GameEvent, GameState};
 use crate::util::rnd;
 use crate::{StResult, TheGame};
 
 const WARP: Component = Component::WarpEngines; // Component #0
 
-pub fn do_warp<R: BufRead, W: WriteColor>(
-    the_game: &mut TheGame,
-    sin: &mut R,
-    sout: &mut W,
-    command: &mut Command,
-    gamecomp: &mut GameState,
-    moved: &mut bool,
-) -> StResult<()> {
-    let mut warp = 0f64;
-    let mut course;
+/// How far damaged warp engines can take the Enterprise in one move, in sectors (warp 0.2 across
+/// the original 8x8 quadrants)
+const DAMAGED_WARP_SECTORS: f64 = 1.6;
 
-    loop {
-        loop {
-            course = getcourse(sin, sout)?;
-            the_game.course = course;
-            if course < 9.0 {
-                break;
-            }
-            beep();
-        }
-        if course >= 1.0 {
-            loop {
-                warp = getwarp(sin, sout)?;
-                if (warp <= 0.0) || (warp > 12.0) {
-                    course = 10.0;
-                    break;
-                }
-                if the_game.damage.is_damaged(WARP.into(), false) && (warp > 0.2) {
-                    write!(sout, "{} DAMAGED; MAX IS 0.2; ", WARP.as_ref())?;
-                    sout.flush()?;
-                    the_game.damage.show_est_repair_time(sout, WARP.into())?;
-                    beep();
-                } else {
-                    break;
-                }
-                beep();
-            }
-        }
-        if course < 9.0 {
-            break;
-        }
+/// The number of sectors that warp 1 crosses
+fn sectors_across(the_game: &TheGame) -> i32 {
+    the_game
+        .sector_map
+        .width()
+        .max(the_game.sector_map.height())
+}
+
+/// The fastest the Enterprise can go with its warp engines damaged, to two decimal places
+fn damaged_max_warp(the_game: &TheGame) -> f64 {
+    (DAMAGED_WARP_SECTORS / sectors_across(the_game) as f64 * 100.0).round() / 100.0
+}
+
+/// Explain why the Enterprise can't move at this warp, if it can't
+pub fn warp_rejection(the_game: &TheGame, course: f64, warp: f64) -> Vec<GameEvent> {
+    let mut events = Vec::new();
+    if course < 1.0 {
+        // Abort move
+        return events;
     }
+    if course.is_nan() || course >= 9.0 || warp.is_nan() || (warp <= 0.0) || (warp > 12.0) {
+        events.push(GameEvent::InvalidOrder);
+    } else if the_game.landing_party != LandingParty::Aboard {
+        // Nobody gets left behind
+        events.push(GameEvent::PartyAshore);
+    } else if the_game.damage.is_damaged(WARP.into(), false) && (warp > damaged_max_warp(the_game))
+    {
+        events.push(GameEvent::WarpLimited {
+            max_warp: damaged_max_warp(the_game),
+            repair_time: the_game.damage.get_damage(WARP),
+        });
+    }
+    events
+}
+
+/// Move the Enterprise, returning whether it moved
+pub fn do_warp(the_game: &mut TheGame, course: f64, warp: f64) -> StResult<bool> {
+    the_game.course = course;
     if course < 1.0 {
         // Abort move
-        return Ok(());
+        return Ok(false);
     }
-    the_game.check_for_hits(sout)?;
+    the_game.check_for_hits()?;
     if the_game.energy <= 0.0 {
         /* Ran out of energy */
-        *gamecomp = (-1).into();
-        return Ok(());
+        the_game.game_state = GameState::Lost;
+        return Ok(false);
     }
 
-    if rnd() <= 0.25 {
-        let x = (rnd() * 6.0).floor() as usize;
-        if rnd() <= 0.5 {
-            beep();
+    if rnd(&mut the_game.rng) <= the_game.game_defs.storm_probability {
+        let x = (rnd(&mut the_game.rng) * COMPONENTS as f64).floor() as usize;
+        if rnd(&mut the_game.rng) <= 0.5 {
             the_game
                 .damage
-                .add_damage(x, (6.0 - rnd() * 5.0).floor() as i32);
+                .add_damage(x, (6.0 - rnd(&mut the_game.rng) * 5.0).floor() as i32);
             let i: Component = x.try_into()?;
-            writeln!(sout, "**SPACE STORM, {} DAMAGED**", i)?;
-            the_game.damage.show_est_repair_time(sout, x)?;
+            the_game.emit(GameEvent::SpaceStorm {
+                component: i,
+                repair_time: the_game.damage.get_damage(i),
+            });
             the_game.damage.add_damage(x, 1);
-            delay(100);
-            beep();
         } else {
             let mut j: i32 = -1;
-            for i in x..6 {
+            for i in x..COMPONENTS {
                 if the_game.damage.is_damaged(i, false) {
                     j = i as i32;
                     break;
@@ -100,36 +98,108 @@ pub fn do_warp<R: BufRead, W: WriteColor>(
             }
             if j >= 0 {
                 the_game.damage.set_damage(j as usize, 1);
-                writeln!(sout, "**SPOCK USED A NEW REPAIR TECHNIQUE**")?;
+                let component: Component = (j as usize).try_into()?;
+                the_game.emit(GameEvent::SpockRepair { component });
             }
         }
     }
-    for i in 0..6 {
+    for i in 0..COMPONENTS {
         if the_game.damage.is_damaged(i, true) && the_game.damage.reduce_and_normalize_damage(i) {
             let component: Component = i.try_into()?;
-            writeln!(sout, "{} ARE FIXED!", component.as_ref())?;
-            beep();
+            the_game.emit(GameEvent::RepairCompleted { component });
         }
     }
-    let n = (warp * 8.0).floor();
+    // Warp 1 crosses a quadrant, and any warp at all moves the Enterprise at least one sector
+    let n = (warp * sectors_across(the_game) as f64).floor().max(1.0);
     the_game.warp = warp;
     the_game.energy = the_game.energy - n - n + 0.5;
     the_game.current_stardate += 1i32;
-    let current_sector = the_game.current_sector();
+    let current_sector = the_game.sector;
     the_game.sector_map[current_sector] = 1;
     if the_game.current_stardate > the_game.game_defs.ending_stardate {
         /* Ran out of time! */
-        *gamecomp = (-1).into();
-        return Ok(());
+        the_game.game_state = GameState::Lost;
+        return Ok(false);
+    }
+    migrate_klingons(the_game)?;
+    scheduled_supernova(the_game)?;
+    besiege_starbases(the_game);
+    if the_game.total_klingons < 1 {
+        // The last of them went up with a star
+        the_game.game_state = GameState::Won;
+        return Ok(false);
+    }
+    // What the Enterprise leaves behind is what the records will remember
+    the_game.record_quadrant(the_game.quadrant);
+    do_path(the_game, Command::WarpEngines, n)?;
+    if the_game.game_state.is_done() {
+        // Lost in a black hole
+        return Ok(false);
     }
-    do_path(the_game, sout, *command, n)?;
-    *command = the_game.saved_command;
-    // let i = n;
     if the_game.energy <= 0.0 {
         // Ran out of energy
-        *gamecomp = (-1).into();
-        return Ok(());
+        the_game.game_state = GameState::Lost;
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::the_game::{new_game, Action, SectorContents, TheGameDefsBuilder};
+
+    #[test]
+    fn test_warp_rejects_nan() -> StResult<()> {
+        let mut the_game = new_game(1);
+        the_game.start()?;
+        let sector = the_game.sector;
+        for (course, warp) in [(f64::NAN, 1.0), (1.0, f64::NAN)].iter() {
+            let action = Action::Warp {
+                course: *course,
+                warp: *warp,
+            };
+            assert_eq!(vec![GameEvent::InvalidOrder], the_game.apply(action)?);
+        }
+        assert_eq!(sector, the_game.sector);
+        Ok(())
+    }
+
+    #[test]
+    fn test_slow_warp_in_small_quadrant_moves_one_sector() -> StResult<()> {
+        let the_game_defs = TheGameDefsBuilder::default()
+            .seed(1)
+            .quadrant_width(4)
+            .quadrant_height(4)
+            .storm_probability(0.0)
+            .build()
+            .unwrap();
+        let mut the_game = TheGame::new(&the_game_defs);
+        the_game.start()?;
+        the_game.damage.fix_damage();
+        for sector in the_game.sector_map.sectors().collect::<Vec<_>>() {
+            the_game.sector_map[sector] = SectorContents::Empty.into();
+        }
+        let start = the_game.sector;
+        the_game.sector_map[start] = SectorContents::Enterprise.into();
+        the_game.total_klingons -= the_game.quadrant_klingons;
+        the_game.quadrant_klingons = 0;
+        let (course, step) = if start.column() < 3 {
+            (1.0, 1)
+        } else {
+            (5.0, -1)
+        };
+
+        // The damaged engines' limit scales with the quadrant, and still gets somewhere
+        the_game.damage.add_damage(WARP.into(), 5);
+        let events = the_game.apply(Action::Warp { course, warp: 0.5 })?;
+        assert!(matches!(
+            events.as_slice(),
+            [GameEvent::WarpLimited { max_warp, .. }] if *max_warp == 0.4
+        ));
+        the_game.apply(Action::Warp { course, warp: 0.2 })?;
+        assert_eq!(start.row(), the_game.sector.row());
+        assert_eq!(start.column() + step, the_game.sector.column());
+        Ok(())
     }
-    *moved = true;
-    Ok(())
 }
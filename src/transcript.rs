@@ -0,0 +1,198 @@
+//! # startrust::transcript
+//!
+//! Record everything the player types during a game, so that the game can be replayed exactly.
+//! A transcript holds the game definitions (with the seed that was actually used) and the
+//! player's answers to every prompt, one per line.
+
+use std::io::{self, BufRead, Cursor, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{StResult, StarTrustError, TheGame, TheGameDefs};
+
+/// The version of the transcript format written by this build. A transcript only replays on a
+/// build that plays the same answers out the same way, so this goes up whenever a prompt is added
+/// or removed, or a change to the rules or to the game's use of random numbers would make a
+/// recorded game turn out differently.
+pub const TRANSCRIPT_FORMAT_VERSION: u64 = 16;
+
+/// A recorded game
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Transcript {
+    version: u64,
+    defs: TheGameDefs,
+    answers: Vec<String>,
+}
+
+impl Transcript {
+    /// Make a transcript of a game from what the player typed while playing it
+    pub fn new(the_game: &TheGame, input: &[u8]) -> Self {
+        let mut defs = the_game.game_defs;
+        defs.seed = Some(the_game.seed());
+        let answers = String::from_utf8_lossy(input)
+            .split('\n')
+            .map(String::from)
+            .collect();
+        Self {
+            version: TRANSCRIPT_FORMAT_VERSION,
+            defs,
+            answers,
+        }
+    }
+
+    pub fn write_to<W: Write>(&self, writer: W) -> StResult<()> {
+        serde_json::to_writer_pretty(writer, self)
+            .map_err(|e| StarTrustError::TranscriptError(format!("{}", e)))
+    }
+
+    pub fn read_from<R: Read>(reader: R) -> StResult<Self> {
+        let transcript: Self = serde_json::from_reader(reader)
+            .map_err(|e| StarTrustError::TranscriptError(format!("{}", e)))?;
+        if transcript.version != TRANSCRIPT_FORMAT_VERSION {
+            return Err(StarTrustError::TranscriptError(format!(
+                "transcript is version {}, but this build only understands version {}",
+                transcript.version, TRANSCRIPT_FORMAT_VERSION
+            )));
+        }
+        Ok(transcript)
+    }
+
+    /// The game as it was before the player's first answer
+    pub fn new_game(&self) -> TheGame {
+        TheGame::new(&self.defs)
+    }
+
+    /// Input that gives the recorded answers, in order
+    pub fn replayer(&self) -> Replayer {
+        Replayer(Cursor::new(self.answers.join("\n").into_bytes()))
+    }
+}
+
+/// Passes input through, keeping a copy of everything that was read
+pub struct Recorder<R> {
+    inner: R,
+    recorded: Vec<u8>,
+}
+
+impl<R: BufRead> Recorder<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            recorded: Vec::new(),
+        }
+    }
+
+    /// Everything read so far
+    pub fn recorded(&self) -> &[u8] {
+        &self.recorded
+    }
+}
+
+impl<R: BufRead> Read for Recorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = self.fill_buf()?;
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Recorder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(available) = self.inner.fill_buf() {
+            self.recorded.extend_from_slice(&available[..amt]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Gives the answers from a transcript. Reading past the last answer is an `UnexpectedEof` error
+/// rather than an endless end of file, so that a transcript that stops mid-game ends the replay.
+pub struct Replayer(Cursor<Vec<u8>>);
+
+impl Replayer {
+    /// Whether every recorded answer has been read
+    pub fn is_finished(&self) -> bool {
+        self.0.position() as usize >= self.0.get_ref().len()
+    }
+}
+
+impl Read for Replayer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = self.fill_buf()?;
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for Replayer {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.is_finished() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "end of transcript",
+            ));
+        }
+        self.0.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.0.consume(amt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use termcolor::NoColor;
+
+    use super::*;
+    use crate::TheGameDefsBuilder;
+
+    #[test]
+    fn test_replay_reproduces_game() -> StResult<()> {
+        let input = b"2\n3\n1\n4.5\n0.5\n6\n-99\ny\nn\n";
+        let the_game_defs = TheGameDefsBuilder::default().build().unwrap();
+        let mut the_game = TheGame::new(&the_game_defs);
+        let mut recorder = Recorder::new(&input[..]);
+        let mut played = NoColor::new(Vec::new());
+        the_game.play(&mut recorder, &mut played)?;
+        assert_eq!(b"2\n3\n1\n4.5\n0.5\n6\n-99\ny", recorder.recorded());
+
+        let mut saved = Vec::new();
+        Transcript::new(&the_game, recorder.recorded()).write_to(&mut saved)?;
+        let transcript = Transcript::read_from(saved.as_slice())?;
+        let mut replayed = NoColor::new(Vec::new());
+        let mut replayer = transcript.replayer();
+        transcript.new_game().play(&mut replayer, &mut replayed)?;
+        assert!(replayer.is_finished());
+        assert_eq!(played.into_inner(), replayed.into_inner());
+        Ok(())
+    }
+
+    #[test]
+    fn test_older_transcript_is_refused() -> StResult<()> {
+        let the_game_defs = TheGameDefsBuilder::default().seed(1).build().unwrap();
+        let mut transcript = Transcript::new(&TheGame::new(&the_game_defs), b"");
+        transcript.version = TRANSCRIPT_FORMAT_VERSION - 1;
+        let mut saved = Vec::new();
+        transcript.write_to(&mut saved)?;
+        assert!(matches!(
+            Transcript::read_from(saved.as_slice()),
+            Err(StarTrustError::TranscriptError(_))
+        ));
+        Ok(())
+    }
+}
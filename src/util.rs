@@ -1,29 +1,79 @@
 //! # startrust::util
 //!
 
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use strum_macros::{AsRefStr, EnumString};
 
-/// Set a random x and y in interval \[0,7\]
-pub fn get_random_x_y() -> (i32, i32) {
-    let x: i32 = thread_rng().gen_range(0..8);
-    let y: i32 = thread_rng().gen_range(0..8);
-    (x, y)
+/// Which pseudo-random number generator a game uses
+#[derive(
+    AsRefStr, Copy, Clone, Debug, Default, Deserialize, EnumString, Eq, PartialEq, Serialize,
+)]
+pub enum RandomMode {
+    /// A modern generator from `rand`
+    #[default]
+    #[strum(serialize = "standard")]
+    Standard,
+    /// The generator from the original game, with all of its quirks
+    #[strum(serialize = "classic")]
+    Classic,
+}
+
+/// The source of every random decision made during one game. Each `TheGame` owns one, so that a
+/// game can be reproduced from its seed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum GameRng {
+    Standard(Box<ChaCha8Rng>),
+    /// The state of the original generator, `rn`
+    Classic(f64),
+}
+
+/// Pick a seed for a game that was not given one
+pub fn new_seed() -> u64 {
+    thread_rng().gen()
+}
+
+/// Set a random x in interval \[0,height) and y in interval \[0,width)
+pub fn get_random_x_y(rng: &mut GameRng, width: i32, height: i32) -> (i32, i32) {
+    match rng {
+        GameRng::Standard(rng) => {
+            let x: i32 = rng.gen_range(0..height);
+            let y: i32 = rng.gen_range(0..width);
+            (x, y)
+        }
+        GameRng::Classic(_) => {
+            let x = (rnd(rng) * height as f64) as i32;
+            let y = (rnd(rng) * width as f64) as i32;
+            (x, y)
+        }
+    }
 } /* End setrndxy */
 
 /* Initialize pseudo-random number generator */
-pub fn rand_init() -> f64 {
-    // Does nothing
-    0.0f64
+pub fn rand_init(seed: u64, mode: RandomMode) -> GameRng {
+    match mode {
+        RandomMode::Standard => GameRng::Standard(Box::new(ChaCha8Rng::seed_from_u64(seed))),
+        RandomMode::Classic => {
+            // The original took a fraction from the system clock. The seed stands in for the
+            // clock, but a fraction of exactly 0 would make every number after it 0 as well.
+            let rn = (seed % 100_000) as f64 / 100_000.0;
+            GameRng::Classic(if rn == 0.0 { 0.5 } else { rn })
+        }
+    }
 } /* End randinit */
 
 /// Generate a new pseudo-random number
-pub fn rnd() -> f64 {
-    // This used to literally be:
-    // ```
-    // rn=frac(rn*777.7);
-    // return rn;
-    // ```
-    thread_rng().gen()
+pub fn rnd(rng: &mut GameRng) -> f64 {
+    match rng {
+        GameRng::Standard(rng) => rng.gen(),
+        GameRng::Classic(rn) => {
+            // rn=frac(rn*777.7);
+            // return rn;
+            *rn = (*rn * 777.7).fract();
+            *rn
+        }
+    }
 } /* End rnd */
 
 /// See if r1 is less than r2, BASIC style
@@ -43,3 +93,31 @@ pub fn gt(r1: f64, r2: f64) -> i32 {
         0 /* BASIC false = 0 */
     }
 } /* End gt */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_numbers() {
+        let mut rng_a = rand_init(1978, RandomMode::Standard);
+        let mut rng_b = rand_init(1978, RandomMode::Standard);
+        for _ in 0..100 {
+            assert_eq!(rnd(&mut rng_a), rnd(&mut rng_b));
+            assert_eq!(
+                get_random_x_y(&mut rng_a, 8, 8),
+                get_random_x_y(&mut rng_b, 8, 8)
+            );
+        }
+    }
+
+    #[test]
+    fn test_classic_rnd() {
+        let mut rng = rand_init(12_345, RandomMode::Classic);
+        let mut rn = 0.12345_f64;
+        for _ in 0..100 {
+            rn = (rn * 777.7).fract();
+            assert_eq!(rn, rnd(&mut rng));
+        }
+    }
+}